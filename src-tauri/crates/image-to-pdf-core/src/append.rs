@@ -0,0 +1,290 @@
+use crate::error::{AppError, Result};
+use crate::import::MAX_TREE_DEPTH;
+use crate::settings::AppendTo;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+/// Page attributes that pages inherit from the nodes above them
+const INHERITED_KEYS: [&[u8]; 3] = [b"Resources", b"CropBox", b"Rotate"];
+
+/// An existing PDF that new pages are added to with an incremental update
+///
+/// The file is written out unchanged and followed by the new objects, the
+/// page tree nodes that gained pages and a cross-reference section pointing
+/// back to the file's own, so earlier content and signatures stay intact.
+pub struct AppendTarget {
+    /// Content of the existing file, written ahead of the update
+    pub original: Vec<u8>,
+    /// Offset of the file's last cross-reference section
+    pub prev_xref: u64,
+    /// Whether the file uses a cross-reference stream, so the update does too
+    pub xref_stream: bool,
+    /// Highest object number in use; new objects are numbered after it
+    pub max_id: u32,
+    /// Trailer entries carried over to the update
+    pub trailer: Dictionary,
+    /// Page tree node that receives the new pages, followed by its ancestors
+    /// up to the root, with `Kids` and `Count` resolved to direct values
+    nodes: Vec<(ObjectId, Dictionary)>,
+    /// Position in the first node's `Kids` where the new pages go
+    position: usize,
+}
+
+impl AppendTarget {
+    /// Read the file at `append_to.path` and find where new pages go
+    pub fn open(append_to: &AppendTo) -> Result<Self> {
+        let original = std::fs::read(&append_to.path)?;
+        Self::load(original, append_to.index)
+    }
+
+    /// Find where pages inserted before page `index` (zero-based) go, or
+    /// pages after the last one when `index` is `None`
+    pub fn load(original: Vec<u8>, index: Option<usize>) -> Result<Self> {
+        let doc = Document::load_mem(&original).map_err(|e| {
+            AppError::PdfGenerationError(format!("Failed to read the PDF to append to: {}", e))
+        })?;
+        if doc.is_encrypted() || doc.encryption_state.is_some() {
+            return Err(AppError::InvalidSettings(
+                "Cannot append to an encrypted PDF".to_string(),
+            ));
+        }
+
+        let tree_error = |e: lopdf::Error| {
+            AppError::PdfGenerationError(format!(
+                "Invalid page tree in the PDF to append to: {}",
+                e
+            ))
+        };
+        let root_id = doc
+            .catalog()
+            .and_then(|catalog| catalog.get(b"Pages"))
+            .and_then(Object::as_reference)
+            .map_err(tree_error)?;
+
+        let pages = doc.get_pages();
+        let (parent_id, before) = match index {
+            None => (root_id, None),
+            Some(index) if index == pages.len() => (root_id, None),
+            Some(index) => {
+                let page_id = *pages.get(&(index as u32 + 1)).ok_or_else(|| {
+                    AppError::InvalidSettings(format!(
+                        "Cannot insert before page {}: the document has {} pages",
+                        index + 1,
+                        pages.len()
+                    ))
+                })?;
+                let parent_id = doc
+                    .get_dictionary(page_id)
+                    .and_then(|page| page.get(b"Parent"))
+                    .and_then(Object::as_reference)
+                    .map_err(tree_error)?;
+                (parent_id, Some(page_id))
+            }
+        };
+
+        // Collect the node and its ancestors with the entries that change
+        let mut nodes = Vec::new();
+        let mut node_id = Some(parent_id);
+        while let Some(id) = node_id {
+            if nodes.len() == MAX_TREE_DEPTH {
+                return Err(AppError::PdfGenerationError(
+                    "Invalid page tree in the PDF to append to: nested too deeply".to_string(),
+                ));
+            }
+            let mut node = doc.get_dictionary(id).map_err(tree_error)?.clone();
+            let count = node
+                .get(b"Count")
+                .and_then(|count| doc.dereference(count))
+                .and_then(|(_, count)| count.as_i64())
+                .map_err(tree_error)?;
+            let kids = node
+                .get(b"Kids")
+                .and_then(|kids| doc.dereference(kids))
+                .and_then(|(_, kids)| kids.as_array())
+                .map_err(tree_error)?
+                .clone();
+            node.set("Count", count);
+            node.set("Kids", kids);
+
+            node_id = node.get(b"Parent").and_then(Object::as_reference).ok();
+            nodes.push((id, node));
+        }
+
+        let kids = nodes[0]
+            .1
+            .get(b"Kids")
+            .and_then(Object::as_array)
+            .map_err(tree_error)?;
+        let position = match before {
+            Some(page_id) => kids
+                .iter()
+                .position(|kid| kid.as_reference().ok() == Some(page_id))
+                .ok_or_else(|| {
+                    AppError::PdfGenerationError(
+                        "Invalid page tree in the PDF to append to: page missing from its parent"
+                            .to_string(),
+                    )
+                })?,
+            None => kids.len(),
+        };
+
+        let mut trailer = Dictionary::new();
+        for key in [b"Root".as_slice(), b"Info", b"ID"] {
+            if let Ok(value) = doc.trailer.get(key) {
+                trailer.set(key, value.clone());
+            }
+        }
+        // A classic section starts with the `xref` keyword, a stream with its
+        // object header
+        let section = original.get(doc.xref_start..).unwrap_or_default();
+        let xref_stream = !section.trim_ascii_start().starts_with(b"xref");
+
+        // Numbers listed as free in the file may not be reused either
+        let size = doc
+            .trailer
+            .get(b"Size")
+            .and_then(Object::as_i64)
+            .unwrap_or(0);
+
+        Ok(AppendTarget {
+            prev_xref: doc.xref_start as u64,
+            xref_stream,
+            max_id: doc.max_id.max(size.saturating_sub(1) as u32),
+            trailer,
+            nodes,
+            position,
+            original,
+        })
+    }
+
+    /// Page tree node the new pages are added to
+    pub fn parent(&self) -> ObjectId {
+        self.nodes[0].0
+    }
+
+    /// Set the attributes that `page` would otherwise inherit from the
+    /// existing page tree to their defaults
+    pub fn isolate_page(&self, page: &mut Dictionary) {
+        for key in INHERITED_KEYS {
+            if page.has(key) || !self.nodes.iter().any(|(_, node)| node.has(key)) {
+                continue;
+            }
+            let default = match key {
+                b"Resources" => Object::Dictionary(Dictionary::new()),
+                b"Rotate" => Object::Integer(0),
+                // Default crop box is the media box
+                _ => match page.get(b"MediaBox") {
+                    Ok(media_box) => media_box.clone(),
+                    Err(_) => continue,
+                },
+            };
+            page.set(key, default);
+        }
+    }
+
+    /// Page tree nodes with `page_ids` inserted and the page counts raised
+    pub fn updated_nodes(&self, page_ids: &[ObjectId]) -> Vec<(ObjectId, Dictionary)> {
+        let mut nodes = self.nodes.clone();
+        for (index, (_, node)) in nodes.iter_mut().enumerate() {
+            if index == 0 {
+                if let Ok(Object::Array(kids)) = node.get_mut(b"Kids") {
+                    let pages = page_ids.iter().map(|&id| Object::Reference(id));
+                    kids.splice(self.position..self.position, pages);
+                }
+            }
+            let count = node.get(b"Count").and_then(Object::as_i64).unwrap_or(0);
+            node.set("Count", count + page_ids.len() as i64);
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+    use lopdf::xref::XrefType;
+
+    /// Two pages under an intermediate node that rotates them, then a third
+    /// page directly under the root
+    fn nested_tree() -> Vec<u8> {
+        let mut doc = Document::with_version("1.4");
+        let root_id = doc.new_object_id();
+        let node_id = doc.new_object_id();
+        let page = |parent: ObjectId| {
+            dictionary! {
+                "Type" => "Page",
+                "Parent" => parent,
+                "MediaBox" => vec![0.into(), 0.into(), 100.into(), 100.into()],
+            }
+        };
+        let first = doc.add_object(page(node_id));
+        let second = doc.add_object(page(node_id));
+        let third = doc.add_object(page(root_id));
+        doc.objects.insert(
+            node_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Parent" => root_id,
+                "Rotate" => 90,
+                "Count" => 2,
+                "Kids" => vec![first.into(), second.into()],
+            }),
+        );
+        doc.objects.insert(
+            root_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Count" => 3,
+                "Kids" => vec![node_id.into(), third.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => root_id });
+        doc.trailer.set("Root", catalog_id);
+
+        let mut data = Vec::new();
+        doc.reference_table.cross_reference_type = XrefType::CrossReferenceTable;
+        doc.save_to(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_append_target_inserts_into_nested_tree() {
+        let target = AppendTarget::load(nested_tree(), Some(1)).unwrap();
+        assert!(!target.xref_stream);
+        assert_eq!(target.max_id, 6);
+        assert_eq!(
+            target.trailer.get(b"Root").unwrap(),
+            &Object::Reference((6, 0))
+        );
+
+        // Inserted before the second page, inside the rotating node
+        assert_eq!(target.parent(), (2, 0));
+        let mut page = dictionary! {
+            "MediaBox" => vec![0.into(), 0.into(), 10.into(), 20.into()],
+            "Resources" => Dictionary::new(),
+        };
+        target.isolate_page(&mut page);
+        assert_eq!(page.get(b"Rotate").unwrap(), &Object::Integer(0));
+        assert!(!page.has(b"CropBox"));
+
+        let nodes = target.updated_nodes(&[(7, 0), (8, 0)]);
+        let kids = nodes[0].1.get(b"Kids").and_then(Object::as_array).unwrap();
+        let kids: Vec<_> = kids.iter().map(|kid| kid.as_reference().unwrap()).collect();
+        assert_eq!(kids, vec![(3, 0), (7, 0), (8, 0), (4, 0)]);
+        let counts: Vec<_> = nodes
+            .iter()
+            .map(|(_, node)| node.get(b"Count").unwrap().as_i64().unwrap())
+            .collect();
+        assert_eq!(counts, vec![4, 5]);
+
+        // Appended after the last page, under the root
+        let target = AppendTarget::load(nested_tree(), Some(3)).unwrap();
+        assert_eq!(target.parent(), (1, 0));
+        assert_eq!(target.position, 2);
+
+        assert!(matches!(
+            AppendTarget::load(nested_tree(), Some(4)),
+            Err(AppError::InvalidSettings(_))
+        ));
+    }
+}
@@ -0,0 +1,1451 @@
+use crate::append::AppendTarget;
+use crate::embed::compress_flate;
+use crate::encryption::{encryption_state, ensure_file_id};
+use crate::error::{AppError, Result};
+use crate::layout::{
+    calculate_image_placement, content_area, get_page_dimensions, grid_cells, placement_matrix,
+    ContentArea,
+};
+use crate::metadata::DocumentInfo;
+use crate::pdfa::{add_output_intent, check_page_size, PDFA_VERSION};
+use crate::placeholder::{font_dictionary, placeholder_operations, PLACEHOLDER_SIZE};
+use crate::prepare::{ImagePreparer, PreparedImage};
+use crate::settings::{
+    Alignment, Compliance, EncryptionAlgorithm, ErrorMode, Margins, PageSize, PdfSettings,
+};
+use crate::utils::orientation::swaps_dimensions;
+use crate::writer::PdfWriter;
+use image::metadata::Orientation as ExifOrientation;
+use lopdf::content::{Content, Operation};
+use lopdf::{
+    dictionary, Dictionary, Document, EncryptionState, Object, ObjectId, SaveOptions, Stream,
+};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Incrementally builds a PDF with one page per image, or several per page
+/// with a grid layout
+///
+/// A builder from [`PdfBuilder::new`] keeps the document in memory until
+/// [`PdfBuilder::write_to`]. One from [`PdfBuilder::stream_to`] writes every
+/// image and page as soon as it is complete and only keeps object offsets.
+/// With [`PdfSettings::append_to`] set, a streaming builder writes the pages
+/// as an incremental update to an existing PDF.
+///
+/// ```no_run
+/// use image_to_pdf_core::{PdfBuilder, PdfSettings};
+///
+/// let logo = std::fs::read("logo.png")?;
+/// let mut output = std::fs::File::create("out.pdf")?;
+///
+/// PdfBuilder::new(PdfSettings::default())?
+///     .add_image("scan.jpg")?
+///     .add_image_bytes(&logo)?
+///     .write_to(&mut output)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct PdfBuilder {
+    doc: Document,
+    pages_id: ObjectId,
+    page_ids: Vec<ObjectId>,
+    settings: PdfSettings,
+    margins: Margins,
+    alignment: Alignment,
+    preparer: Arc<ImagePreparer>,
+    info: DocumentInfo,
+    info_id: ObjectId,
+    metadata_id: ObjectId,
+    /// Security handler applied to a copy of the document when writing
+    encryption: Option<EncryptionState>,
+    compliance: Option<Compliance>,
+    /// Image XObjects by content hash, so repeated images are embedded once
+    embedded: HashMap<[u8; 32], ObjectId>,
+    duplicates_collapsed: usize,
+    /// Image data left out because it repeated an embedded image
+    duplicate_bytes_saved: u64,
+    /// Images waiting for the current page to fill up
+    pending: Vec<PlacedImage>,
+    /// Font for placeholder text, added with the first placeholder
+    placeholder_font: Option<ObjectId>,
+    /// Output that finished objects are streamed to, if any
+    output: Option<PdfWriter>,
+    /// Existing PDF the pages are added to, if any
+    append: Option<AppendTarget>,
+}
+
+/// An embedded image, or a placeholder for one, waiting to be drawn on a page
+struct PlacedImage {
+    /// Physical size of the upright image, in points
+    width: f32,
+    height: f32,
+    content: CellContent,
+}
+
+enum CellContent {
+    /// Image XObject and the transform still to be applied to its pixels
+    Image {
+        image_id: ObjectId,
+        orientation: ExifOrientation,
+    },
+    /// Explanation drawn in place of an image that could not be added
+    Placeholder { name: String, reason: String },
+}
+
+impl PdfBuilder {
+    /// Create a builder, validating page size and colour settings up front
+    pub fn new(settings: PdfSettings) -> Result<Self> {
+        // Fixed page sizes do not depend on the image, so this catches bad custom
+        // dimensions and margins that do not fit on the page
+        let margins = settings.margins.clone().unwrap_or_default();
+        let (page_width, page_height) = get_page_dimensions(&settings, (0.0, 0.0))?;
+        match (&settings.page_size, &settings.grid) {
+            (PageSize::MatchImage, Some(_)) => {
+                return Err(AppError::InvalidSettings(
+                    "Grid layouts need a fixed page size".to_string(),
+                ));
+            }
+            (PageSize::MatchImage, None) => {}
+            (_, grid) => {
+                let area = content_area(page_width, page_height, &margins)?;
+                if let Some(grid) = grid {
+                    grid_cells(&area, grid)?;
+                }
+            }
+        }
+        let preparer = ImagePreparer::from_settings(&settings)?;
+        let info = DocumentInfo::from_settings(settings.metadata.as_ref())?;
+
+        let compliance = settings.compliance;
+        if compliance.is_some() && settings.encryption.is_some() {
+            return Err(AppError::InvalidSettings(
+                "PDF/A output cannot be encrypted".to_string(),
+            ));
+        }
+        // Placeholder text uses a standard font that PDF/A would need embedded
+        if compliance.is_some() && settings.on_error == Some(ErrorMode::Placeholder) {
+            return Err(AppError::InvalidSettings(
+                "PDF/A output cannot contain placeholder pages".to_string(),
+            ));
+        }
+
+        let append = match &settings.append_to {
+            Some(_) if settings.encryption.is_some() => {
+                return Err(AppError::InvalidSettings(
+                    "Pages appended to an existing PDF cannot be encrypted".to_string(),
+                ));
+            }
+            Some(_) if compliance.is_some() => {
+                return Err(AppError::InvalidSettings(
+                    "PDF/A output cannot be appended to an existing PDF".to_string(),
+                ));
+            }
+            Some(append_to) => Some(AppendTarget::open(append_to)?),
+            None => None,
+        };
+
+        // Create new PDF document
+        let mut doc = Document::with_version("1.5");
+
+        let (pages_id, info_id, metadata_id) = match &append {
+            // New objects are numbered after the existing ones; the file keeps
+            // its own catalog, Info dictionary and XMP metadata
+            Some(target) => {
+                doc.max_id = target.max_id;
+                doc.trailer = target.trailer.clone();
+                (target.parent(), (0, 0), (0, 0))
+            }
+            None => {
+                let pages_id = doc.new_object_id();
+                let metadata_id = doc.new_object_id();
+                let mut catalog = dictionary! {
+                    "Type" => "Catalog",
+                    "Pages" => pages_id,
+                    "Metadata" => metadata_id,
+                };
+                if let Some(Compliance::PdfA2b) = compliance {
+                    doc.version = PDFA_VERSION.to_string();
+                    ensure_file_id(&mut doc)?;
+                    let intent_id = add_output_intent(&mut doc)?;
+                    catalog.set("OutputIntents", vec![Object::Reference(intent_id)]);
+                }
+                let catalog_id = doc.add_object(catalog);
+                let info_id = doc.new_object_id();
+                doc.trailer.set("Root", catalog_id);
+                doc.trailer.set("Info", info_id);
+                (pages_id, info_id, metadata_id)
+            }
+        };
+
+        // AES-128 needs PDF 1.6 and AES-256 needs PDF 2.0
+        let encryption = match &settings.encryption {
+            Some(encryption) => {
+                doc.version = match encryption.algorithm {
+                    EncryptionAlgorithm::Aes128 => "1.6",
+                    EncryptionAlgorithm::Aes256 => "2.0",
+                }
+                .to_string();
+                ensure_file_id(&mut doc)?;
+                Some(encryption_state(&doc, encryption)?)
+            }
+            None => None,
+        };
+
+        let alignment = settings.alignment.unwrap_or_default();
+
+        Ok(PdfBuilder {
+            doc,
+            pages_id,
+            page_ids: Vec::new(),
+            settings,
+            margins,
+            alignment,
+            preparer: Arc::new(preparer),
+            info,
+            info_id,
+            metadata_id,
+            encryption,
+            compliance,
+            embedded: HashMap::new(),
+            duplicates_collapsed: 0,
+            duplicate_bytes_saved: 0,
+            pending: Vec::new(),
+            placeholder_font: None,
+            output: None,
+            append,
+        })
+    }
+
+    /// Create a builder that writes each image and page to `writer` as soon as
+    /// it is complete, so memory use stays bounded by the largest single image
+    ///
+    /// Call [`PdfBuilder::finish`] to write the page tree and cross-reference
+    /// table. When appending, the existing file is written to `writer` first,
+    /// unchanged.
+    ///
+    /// ```no_run
+    /// use image_to_pdf_core::{PdfBuilder, PdfSettings};
+    ///
+    /// let output = std::io::BufWriter::new(std::fs::File::create("out.pdf")?);
+    /// let mut builder = PdfBuilder::stream_to(PdfSettings::default(), output)?;
+    /// for path in ["1.jpg", "2.jpg"] {
+    ///     builder.add_image(path)?;
+    /// }
+    /// builder.finish()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stream_to<W: Write + Send + 'static>(settings: PdfSettings, writer: W) -> Result<Self> {
+        let mut builder = Self::new(settings)?;
+        let object_streams = builder.settings.object_streams.unwrap_or(false);
+        let encryption = builder.encryption.take();
+
+        builder.output = Some(match &mut builder.append {
+            Some(target) => {
+                let output = PdfWriter::append(Box::new(writer), target, object_streams)?;
+                target.original = Vec::new();
+                output
+            }
+            None => PdfWriter::new(Box::new(writer), &builder.doc, encryption, object_streams)?,
+        });
+        Ok(builder)
+    }
+
+    /// Add an image file as a new page
+    pub fn add_image<P: AsRef<Path>>(&mut self, path: P) -> Result<&mut Self> {
+        let preparer = self.preparer();
+        let input = preparer.read_file(path.as_ref())?;
+        preparer.prepare_each(input, |image| self.add_prepared(image).map(drop))?;
+        Ok(self)
+    }
+
+    /// Add an in-memory image as a new page, detecting its format from the content
+    pub fn add_image_bytes(&mut self, data: &[u8]) -> Result<&mut Self> {
+        let preparer = self.preparer();
+        let input = preparer.read_bytes(data)?;
+        preparer.prepare_each(input, |image| self.add_prepared(image).map(drop))?;
+        Ok(self)
+    }
+
+    /// Add a page (or grid cell) stating that the image `name` could not be
+    /// added and why
+    pub fn add_placeholder(&mut self, name: &str, error: &AppError) -> Result<&mut Self> {
+        if self.placeholder_font.is_none() {
+            self.placeholder_font = Some(self.doc.add_object(font_dictionary()));
+        }
+
+        let (width, height) = PLACEHOLDER_SIZE;
+        let content = CellContent::Placeholder {
+            name: name.to_string(),
+            reason: error.to_string(),
+        };
+        self.place(PlacedImage {
+            width,
+            height,
+            content,
+        })?;
+        Ok(self)
+    }
+
+    /// Settings-only half of adding images, which can run on other threads
+    pub(crate) fn preparer(&self) -> Arc<ImagePreparer> {
+        Arc::clone(&self.preparer)
+    }
+
+    /// Embed a prepared image as a page or grid cell, returning the bytes of
+    /// image data added to the file
+    ///
+    /// Pages of a PDF input are copied as they are, after the page being
+    /// filled.
+    pub(crate) fn add_prepared(&mut self, image: PreparedImage) -> Result<u64> {
+        if image.is_pdf_pages() {
+            let embedded_bytes = image.encoded_len();
+            self.add_pdf_pages(image)?;
+            return Ok(embedded_bytes);
+        }
+        // Duplicates reuse an image already in the file and add no image data
+        let embedded_bytes = if self.embedded.contains_key(&image.key) {
+            0
+        } else {
+            image.encoded_len()
+        };
+        let (width, height) = image.size_points();
+        let orientation = image.orientation;
+        // Physical size of the upright image
+        let (width, height) = if swaps_dimensions(orientation) {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        // Lay out the page an image starts before embedding it, so an image
+        // that does not fit leaves no object behind
+        if self.pending.is_empty() {
+            self.page_layout(width, height)?;
+        }
+        let image_id = self.embed_once(image)?;
+
+        self.place(PlacedImage {
+            width,
+            height,
+            content: CellContent::Image {
+                image_id,
+                orientation,
+            },
+        })?;
+        Ok(embedded_bytes)
+    }
+
+    /// Number of pages added so far, including a partly filled grid page
+    pub fn page_count(&self) -> usize {
+        self.page_ids.len() + usize::from(!self.pending.is_empty())
+    }
+
+    /// Number of images that reused an identical, already embedded image
+    pub fn duplicates_collapsed(&self) -> usize {
+        self.duplicates_collapsed
+    }
+
+    /// Bytes of image data that duplicates did not add to the file
+    pub fn duplicate_bytes_saved(&self) -> u64 {
+        self.duplicate_bytes_saved
+    }
+
+    /// Bytes written so far by a streaming builder (always 0 otherwise)
+    pub fn bytes_written(&self) -> u64 {
+        self.output.as_ref().map_or(0, PdfWriter::position)
+    }
+
+    /// Complete a streaming builder: write the last page, the page tree,
+    /// metadata and cross-reference section, then flush the output
+    pub fn finish(mut self) -> Result<()> {
+        let Some(output) = self.output.take() else {
+            return Err(AppError::PdfGenerationError(
+                "Only a streaming builder can be finished; use write_to".to_string(),
+            ));
+        };
+
+        self.flush_page()?;
+        self.finish_pages();
+        if self.append.is_none() {
+            self.write_metadata();
+        }
+        output.finish(&mut self.doc)
+    }
+
+    /// Serialize the document to `writer`
+    pub fn write_to<W: Write>(&mut self, mut writer: W) -> Result<()> {
+        if self.output.is_some() {
+            return Err(AppError::PdfGenerationError(
+                "A streaming builder writes its own output; use finish".to_string(),
+            ));
+        }
+        if self.append.is_some() {
+            return Err(AppError::PdfGenerationError(
+                "Appending to a PDF needs a streaming builder; use stream_to".to_string(),
+            ));
+        }
+        self.flush_page()?;
+        self.finish_pages();
+        self.write_metadata();
+
+        let save_error = |e: &dyn std::fmt::Display| {
+            AppError::PdfGenerationError(format!("Failed to save PDF: {}", e))
+        };
+
+        match &self.encryption {
+            // Encrypt a copy so the builder can still be written again. Encrypted
+            // objects cannot be packed into object streams afterwards.
+            Some(state) => {
+                let mut doc = self.doc.clone();
+                doc.encrypt(state).map_err(|e| {
+                    AppError::PdfGenerationError(format!("Failed to encrypt PDF: {}", e))
+                })?;
+                doc.save_to(&mut writer).map_err(|e| save_error(&e))
+            }
+            None if self.settings.object_streams.unwrap_or(false) => {
+                // Saving allocates IDs for the object streams and turns the trailer
+                // into the xref stream dictionary; undo both for later writes
+                let (max_id, trailer) = (self.doc.max_id, self.doc.trailer.clone());
+                let options = SaveOptions {
+                    use_object_streams: true,
+                    use_xref_streams: true,
+                    ..Default::default()
+                };
+                let saved = self.doc.save_with_options(&mut writer, options);
+                self.doc.max_id = max_id;
+                self.doc.trailer = trailer;
+                saved.map_err(|e| save_error(&e))
+            }
+            None => self.doc.save_to(&mut writer).map_err(|e| save_error(&e)),
+        }
+    }
+
+    /// Save the document to a file
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = std::io::BufWriter::new(file);
+
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Document under construction, for inspecting what has been embedded
+    #[cfg(test)]
+    pub(crate) fn document(&self) -> &Document {
+        &self.doc
+    }
+
+    /// Reuse the image XObject embedded for identical content, or embed it now
+    fn embed_once(&mut self, image: PreparedImage) -> Result<ObjectId> {
+        if let Some(&image_id) = self.embedded.get(&image.key) {
+            self.duplicates_collapsed += 1;
+            self.duplicate_bytes_saved += image.encoded_len();
+            return Ok(image_id);
+        }
+
+        let key = image.key;
+        let image_id = image.embed(&mut self.doc)?;
+        self.embedded.insert(key, image_id);
+        Ok(image_id)
+    }
+
+    /// Copy the pages of a PDF input to the end of the document
+    fn add_pdf_pages(&mut self, pdf: PreparedImage) -> Result<()> {
+        self.flush_page()?;
+        let page_ids = pdf.import_pages(&mut self.doc, self.pages_id)?;
+        if let Some(target) = &self.append {
+            for &page_id in &page_ids {
+                if let Ok(Object::Dictionary(page)) = self.doc.get_object_mut(page_id) {
+                    target.isolate_page(page);
+                }
+            }
+        }
+        self.page_ids.extend(page_ids);
+
+        if let Some(output) = &mut self.output {
+            output.write_objects(&mut self.doc)?;
+        }
+        Ok(())
+    }
+
+    /// Queue an image or placeholder, adding the page once it is full
+    fn place(&mut self, placed: PlacedImage) -> Result<()> {
+        self.pending.push(placed);
+
+        if self.pending.len() >= self.cells_per_page() {
+            self.flush_page()?;
+        }
+        // Only the IDs of written images are needed from here on
+        if let Some(output) = &mut self.output {
+            output.write_objects(&mut self.doc)?;
+        }
+        Ok(())
+    }
+
+    fn cells_per_page(&self) -> usize {
+        self.settings
+            .grid
+            .as_ref()
+            // The cell count was checked against a cap in `new`
+            .map_or(1, |grid| grid.rows as usize * grid.columns as usize)
+    }
+
+    /// Page size and cells of a page whose first image is `width` × `height` points
+    fn page_layout(&self, width: f32, height: f32) -> Result<(f32, f32, Vec<ContentArea>)> {
+        // Calculate page size from the first image; pages matching the image grow by the margins
+        let [top, right, bottom, left] = self.margins.to_points();
+        let (page_width, page_height) =
+            get_page_dimensions(&self.settings, (width + left + right, height + top + bottom))?;
+        if self.compliance.is_some() {
+            check_page_size(page_width, page_height)?;
+        }
+        let area = content_area(page_width, page_height, &self.margins)?;
+        let cells = match &self.settings.grid {
+            Some(grid) => grid_cells(&area, grid)?,
+            None => vec![area],
+        };
+        Ok((page_width, page_height, cells))
+    }
+
+    /// Add a page that draws the pending images, one per grid cell, according to the fit mode
+    fn flush_page(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let images = std::mem::take(&mut self.pending);
+        let (page_width, page_height, cells) = self.page_layout(images[0].width, images[0].height)?;
+
+        // Create content stream to place the images
+        let mut operations = Vec::new();
+        let mut xobjects = Dictionary::new();
+        let mut fonts = Dictionary::new();
+
+        for (index, (image, cell)) in images.iter().zip(&cells).enumerate() {
+            let (image_id, orientation) = match &image.content {
+                CellContent::Image {
+                    image_id,
+                    orientation,
+                } => (*image_id, *orientation),
+                CellContent::Placeholder { name, reason } => {
+                    if let Some(font_id) = self.placeholder_font {
+                        fonts.set("F1", font_id);
+                    }
+                    operations.extend(placeholder_operations(cell, "F1", name, reason));
+                    continue;
+                }
+            };
+
+            let name = format!("Im{}", index + 1);
+            let placement = calculate_image_placement(
+                image.width,
+                image.height,
+                cell,
+                &self.settings.fit_mode,
+                self.alignment,
+            );
+
+            operations.extend([
+                // Save graphics state
+                Operation::new("q", vec![]),
+                // Clip to the cell so overflowing images respect margins and gutters
+                Operation::new(
+                    "re",
+                    vec![cell.x.into(), cell.y.into(), cell.width.into(), cell.height.into()],
+                ),
+                Operation::new("W", vec![]),
+                Operation::new("n", vec![]),
+                // Transform matrix: [a b c d e f]
+                // scales the unit image to the placement, rotating or flipping it upright
+                Operation::new(
+                    "cm",
+                    placement_matrix(&placement, orientation)
+                        .iter()
+                        .map(|&value| value.into())
+                        .collect(),
+                ),
+                // Draw image
+                Operation::new("Do", vec![Object::Name(name.clone().into_bytes())]),
+                // Restore graphics state
+                Operation::new("Q", vec![]),
+            ]);
+            xobjects.set(name, image_id);
+        }
+
+        let content_data = Content { operations }.encode()
+            .map_err(|e| AppError::PdfGenerationError(format!("Failed to encode content: {}", e)))?;
+        let content_data = compress_flate(&content_data)?;
+
+        let content_id = self.doc.add_object(Stream::new(
+            dictionary! {
+                "Filter" => "FlateDecode",
+                "Length" => content_data.len() as i64,
+            },
+            content_data,
+        ));
+
+        let mut resources = dictionary! {
+            "XObject" => xobjects,
+        };
+        if !fonts.is_empty() {
+            resources.set("Font", fonts);
+        }
+
+        // Create page
+        let mut page = dictionary! {
+            "Type" => "Page",
+            "Parent" => self.pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), page_width.into(), page_height.into()],
+            "Contents" => content_id,
+            "Resources" => resources,
+        };
+        if let Some(target) = &self.append {
+            target.isolate_page(&mut page);
+        }
+        let page_id = self.doc.add_object(page);
+
+        self.page_ids.push(page_id);
+        Ok(())
+    }
+
+    /// (Re)build the Info dictionary and the XMP metadata stream
+    fn write_metadata(&mut self) {
+        let xmp = self.info.xmp_packet(self.compliance).into_bytes();
+        let metadata = Stream::new(
+            dictionary! {
+                "Type" => "Metadata",
+                "Subtype" => "XML",
+                "Length" => xmp.len() as i64,
+            },
+            xmp,
+        );
+
+        self.doc.objects.insert(self.info_id, Object::Dictionary(self.info.info_dictionary()));
+        self.doc.objects.insert(self.metadata_id, Object::Stream(metadata));
+    }
+
+    /// (Re)build the Pages tree from the pages added so far
+    ///
+    /// When appending, only the existing nodes that gain pages are rewritten.
+    fn finish_pages(&mut self) {
+        if let Some(target) = &self.append {
+            for (id, node) in target.updated_nodes(&self.page_ids) {
+                self.doc.objects.insert(id, Object::Dictionary(node));
+            }
+            return;
+        }
+        let pages_dict = dictionary! {
+            "Type" => "Pages",
+            "Count" => self.page_ids.len() as u32,
+            "Kids" => self.page_ids.iter().map(|&id| Object::Reference(id)).collect::<Vec<_>>(),
+        };
+
+        self.doc.objects.insert(self.pages_id, Object::Dictionary(pages_dict));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::FrameSelection;
+    use image::ImageFormat;
+
+    fn png_bytes(img: image::DynamicImage) -> Vec<u8> {
+        let mut data = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut data), ImageFormat::Png)
+            .unwrap();
+        data
+    }
+
+    fn media_box(builder: &PdfBuilder, index: usize) -> Vec<f32> {
+        let page = builder.document().get_dictionary(builder.page_ids[index]).unwrap();
+        page.get(b"MediaBox")
+            .and_then(Object::as_array)
+            .unwrap()
+            .iter()
+            .map(|value| value.as_float().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_builder_writes_one_page_per_image() {
+        let png = png_bytes(image::DynamicImage::new_rgb8(30, 20));
+        let mut output = Vec::new();
+
+        PdfBuilder::new(PdfSettings::default())
+            .unwrap()
+            .add_image_bytes(&png)
+            .unwrap()
+            .add_image_bytes(&png)
+            .unwrap()
+            .write_to(&mut output)
+            .unwrap();
+
+        let doc = Document::load_mem(&output).unwrap();
+        assert_eq!(doc.get_pages().len(), 2);
+    }
+
+    #[test]
+    fn test_builder_writes_info_and_xmp() {
+        let settings = PdfSettings {
+            metadata: Some(crate::settings::DocumentMetadata {
+                title: Some("Receipts".to_string()),
+                author: Some("Accounting".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PdfBuilder::new(settings)
+            .unwrap()
+            .add_image_bytes(&png_bytes(image::DynamicImage::new_rgb8(4, 4)))
+            .unwrap()
+            .write_to(&mut output)
+            .unwrap();
+
+        let doc = Document::load_mem(&output).unwrap();
+        let info_id = doc.trailer.get(b"Info").and_then(Object::as_reference).unwrap();
+        let info = doc.get_dictionary(info_id).unwrap();
+        assert_eq!(info.get(b"Title").unwrap().as_str().unwrap(), b"Receipts");
+        assert!(info.get(b"CreationDate").is_ok());
+
+        let metadata_id = doc.catalog().unwrap().get(b"Metadata").and_then(Object::as_reference).unwrap();
+        let xmp = doc.get_object(metadata_id).and_then(Object::as_stream).unwrap();
+        assert_eq!(xmp.dict.get(b"Subtype").unwrap().as_name().unwrap(), b"XML");
+        assert!(String::from_utf8_lossy(&xmp.content).contains("<rdf:li>Accounting</rdf:li>"));
+    }
+
+    #[test]
+    fn test_encrypted_output_round_trips() {
+        let image = png_bytes(image::DynamicImage::new_rgb8(6, 4));
+        let build = |encryption: Option<crate::settings::Encryption>| {
+            let settings = PdfSettings {
+                encryption,
+                ..Default::default()
+            };
+            let mut output = Vec::new();
+            PdfBuilder::new(settings)
+                .unwrap()
+                .add_image_bytes(&image)
+                .unwrap()
+                .write_to(&mut output)
+                .unwrap();
+            output
+        };
+        let image_content = |doc: &Document| {
+            let page_id = doc.page_iter().next().unwrap();
+            let resources = doc.get_dictionary(page_id).unwrap().get(b"Resources").unwrap();
+            let xobjects = resources.as_dict().unwrap().get(b"XObject").unwrap().as_dict().unwrap();
+            let image_id = xobjects.get(b"Im1").and_then(Object::as_reference).unwrap();
+            doc.get_object(image_id).and_then(Object::as_stream).unwrap().content.clone()
+        };
+
+        let plain = Document::load_mem(&build(None)).unwrap();
+
+        for algorithm in [EncryptionAlgorithm::Aes128, EncryptionAlgorithm::Aes256] {
+            let output = build(Some(crate::settings::Encryption {
+                user_password: "user secret".to_string(),
+                owner_password: Some("owner secret".to_string()),
+                algorithm,
+                permissions: crate::settings::Permissions {
+                    print: true,
+                    copy: false,
+                    modify: false,
+                },
+            }));
+
+            assert!(Document::load_mem_with_password(&output, "wrong").is_err());
+
+            let doc = Document::load_mem_with_password(&output, "user secret").unwrap();
+            assert_eq!(doc.get_pages().len(), 1);
+            assert_eq!(image_content(&doc), image_content(&plain));
+
+            // Decrypting drops the security handler, so read it from the raw file
+            let raw = Document::load_mem(&output).unwrap();
+            let encrypt_id = raw.trailer.get(b"Encrypt").and_then(Object::as_reference).unwrap();
+            let encrypt = raw.get_dictionary(encrypt_id).unwrap();
+            let p = encrypt.get(b"P").and_then(Object::as_i64).unwrap();
+            assert_ne!(p & (1 << 2), 0, "printing allowed");
+            assert_eq!(p & (1 << 4), 0, "copying denied");
+        }
+    }
+
+    #[test]
+    fn test_pdfa_output() {
+        let settings = PdfSettings {
+            compliance: Some(Compliance::PdfA2b),
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        PdfBuilder::new(settings)
+            .unwrap()
+            .add_image_bytes(&png_bytes(image::DynamicImage::new_rgba8(4, 4)))
+            .unwrap()
+            .write_to(&mut output)
+            .unwrap();
+
+        let doc = Document::load_mem(&output).unwrap();
+        assert_eq!(doc.version, "1.7");
+        assert_eq!(doc.trailer.get(b"ID").and_then(Object::as_array).unwrap().len(), 2);
+
+        let catalog = doc.catalog().unwrap();
+        let intents = catalog.get(b"OutputIntents").and_then(Object::as_array).unwrap();
+        let intent = doc.get_dictionary(intents[0].as_reference().unwrap()).unwrap();
+        assert_eq!(intent.get(b"S").unwrap().as_name().unwrap(), b"GTS_PDFA1");
+        let profile_id = intent.get(b"DestOutputProfile").and_then(Object::as_reference).unwrap();
+        let profile = doc.get_object(profile_id).and_then(Object::as_stream).unwrap();
+        assert_eq!(profile.dict.get(b"N").unwrap().as_i64().unwrap(), 3);
+
+        let metadata_id = catalog.get(b"Metadata").and_then(Object::as_reference).unwrap();
+        let xmp = doc.get_object(metadata_id).and_then(Object::as_stream).unwrap();
+        assert!(String::from_utf8_lossy(&xmp.content).contains("<pdfaid:part>2</pdfaid:part>"));
+    }
+
+    #[test]
+    fn test_pdfa_refuses_what_it_cannot_express() {
+        let encrypted = PdfSettings {
+            compliance: Some(Compliance::PdfA2b),
+            encryption: Some(Default::default()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            PdfBuilder::new(encrypted),
+            Err(AppError::InvalidSettings(_))
+        ));
+
+        // Placeholder text uses a font that is not embedded
+        let placeholders = PdfSettings {
+            compliance: Some(Compliance::PdfA2b),
+            on_error: Some(ErrorMode::Placeholder),
+            ..Default::default()
+        };
+        assert!(matches!(
+            PdfBuilder::new(placeholders),
+            Err(AppError::InvalidSettings(_))
+        ));
+
+        // A page matching a 250-inch wide image exceeds the PDF/A size limit
+        let oversized = PdfSettings {
+            compliance: Some(Compliance::PdfA2b),
+            page_size: PageSize::MatchImage,
+            fallback_dpi: Some(1.0),
+            optimize_images: Some(false),
+            ..Default::default()
+        };
+        let mut builder = PdfBuilder::new(oversized).unwrap();
+        let result = builder.add_image_bytes(&png_bytes(image::DynamicImage::new_rgb8(250, 10)));
+        assert!(matches!(result, Err(AppError::NotCompliant(_))));
+    }
+
+    #[test]
+    fn test_identical_images_are_embedded_once() {
+        let cover = png_bytes(image::DynamicImage::new_rgb8(8, 8));
+        let other = png_bytes(image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+            8,
+            8,
+            image::Rgb([255, 0, 0]),
+        )));
+
+        let mut builder = PdfBuilder::new(PdfSettings::default()).unwrap();
+        builder
+            .add_image_bytes(&cover)
+            .unwrap()
+            .add_image_bytes(&other)
+            .unwrap()
+            .add_image_bytes(&cover)
+            .unwrap();
+
+        assert_eq!(builder.page_count(), 3);
+        assert_eq!(builder.duplicates_collapsed(), 1);
+
+        let image_ids: Vec<_> = builder
+            .page_ids
+            .iter()
+            .map(|&page_id| {
+                let page = builder.document().get_dictionary(page_id).unwrap();
+                let resources = page.get(b"Resources").and_then(Object::as_dict).unwrap();
+                let xobjects = resources.get(b"XObject").and_then(Object::as_dict).unwrap();
+                xobjects.get(b"Im1").and_then(Object::as_reference).unwrap()
+            })
+            .collect();
+        assert_eq!(image_ids[0], image_ids[2]);
+        assert_ne!(image_ids[0], image_ids[1]);
+    }
+
+    #[test]
+    fn test_compressed_content_and_object_streams_shrink_output() {
+        let image = png_bytes(image::DynamicImage::new_rgb8(2, 2));
+        let build = |object_streams: bool| {
+            let settings = PdfSettings {
+                object_streams: Some(object_streams),
+                grid: Some(crate::settings::GridLayout {
+                    rows: 3,
+                    columns: 3,
+                    gutter: 0.0,
+                    unit: crate::settings::LengthUnit::Pt,
+                    fill_order: crate::settings::FillOrder::RowMajor,
+                }),
+                ..Default::default()
+            };
+            let mut builder = PdfBuilder::new(settings).unwrap();
+            for _ in 0..90 {
+                builder.add_image_bytes(&image).unwrap();
+            }
+            let mut output = Vec::new();
+            builder.write_to(&mut output).unwrap();
+            (builder, output)
+        };
+
+        let (builder, classic) = build(false);
+        let page_id = builder.page_ids[0];
+        let contents = builder.document().get_dictionary(page_id).unwrap().get(b"Contents");
+        let content = builder
+            .document()
+            .get_object(contents.and_then(Object::as_reference).unwrap())
+            .and_then(Object::as_stream)
+            .unwrap();
+        assert_eq!(content.dict.get(b"Filter").unwrap().as_name().unwrap(), b"FlateDecode");
+        let decoded = content.decompressed_content().unwrap();
+        assert!(content.content.len() * 2 < decoded.len());
+
+        let (mut builder, packed) = build(true);
+        assert!(
+            packed.len() * 10 < classic.len() * 8,
+            "object streams: {} bytes, classic: {} bytes",
+            packed.len(),
+            classic.len()
+        );
+        let doc = Document::load_mem(&packed).unwrap();
+        assert_eq!(doc.get_pages().len(), 10);
+
+        // Writing again gives the same file
+        let mut again = Vec::new();
+        builder.write_to(&mut again).unwrap();
+        assert_eq!(again.len(), packed.len());
+    }
+
+    /// Output shared between a streaming builder and the test
+    #[derive(Clone, Default)]
+    struct SharedBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_streaming_builder_keeps_no_objects_in_memory() {
+        let images: Vec<_> = (0..5u8)
+            .map(|i| {
+                png_bytes(image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+                    8,
+                    8,
+                    image::Rgb([i * 40, 0, 0]),
+                )))
+            })
+            .collect();
+        let encryption = crate::settings::Encryption {
+            user_password: "secret".to_string(),
+            owner_password: None,
+            algorithm: EncryptionAlgorithm::Aes256,
+            permissions: Default::default(),
+        };
+        let image_contents = |doc: &Document| -> Vec<Vec<u8>> {
+            doc.page_iter()
+                .map(|page_id| {
+                    let resources = doc.get_dictionary(page_id).unwrap().get(b"Resources").unwrap();
+                    let xobjects = resources.as_dict().unwrap().get(b"XObject").unwrap();
+                    let image_id = xobjects.as_dict().unwrap().get(b"Im1").unwrap();
+                    let image = doc.get_object(image_id.as_reference().unwrap()).unwrap();
+                    image.as_stream().unwrap().content.clone()
+                })
+                .collect()
+        };
+
+        for (encryption, object_streams) in [
+            (None, false),
+            (None, true),
+            (Some(encryption.clone()), false),
+            (Some(encryption), true),
+        ] {
+            let settings = PdfSettings {
+                optimize_images: Some(false),
+                encryption: encryption.clone(),
+                object_streams: Some(object_streams),
+                metadata: Some(crate::settings::DocumentMetadata {
+                    title: Some("Streamed".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+
+            let mut in_memory = PdfBuilder::new(settings.clone()).unwrap();
+            let output = SharedBuffer::default();
+            let mut streaming = PdfBuilder::stream_to(settings, output.clone()).unwrap();
+            for image in &images {
+                in_memory.add_image_bytes(image).unwrap();
+                streaming.add_image_bytes(image).unwrap();
+                assert!(streaming.document().objects.is_empty());
+            }
+            assert!(streaming.bytes_written() > 0);
+            assert!(streaming.write_to(Vec::new()).is_err());
+            streaming.finish().unwrap();
+
+            let mut expected = Vec::new();
+            in_memory.write_to(&mut expected).unwrap();
+            let streamed = output.0.lock().unwrap().clone();
+            let packed = object_streams && encryption.is_none();
+            let contains = |needle: &[u8]| streamed.windows(needle.len()).any(|w| w == needle);
+            assert_eq!(contains(b"/Type /ObjStm"), packed);
+            assert_eq!(contains(b"\nxref\n"), !packed);
+            let load = |data: &[u8]| match encryption {
+                Some(_) => Document::load_mem_with_password(data, "secret").unwrap(),
+                None => Document::load_mem(data).unwrap(),
+            };
+            let (expected, doc) = (load(&expected), load(&streamed));
+
+            assert_eq!(doc.get_pages().len(), 5);
+            assert_eq!(image_contents(&doc), image_contents(&expected));
+            let info_id = doc.trailer.get(b"Info").and_then(Object::as_reference).unwrap();
+            let title = doc.get_dictionary(info_id).unwrap().get(b"Title").unwrap();
+            assert_eq!(lopdf::decode_text_string(title).unwrap(), "Streamed");
+        }
+
+        assert!(PdfBuilder::new(PdfSettings::default()).unwrap().finish().is_err());
+    }
+
+    #[test]
+    fn test_builder_rejects_unknown_bytes() {
+        let mut builder = PdfBuilder::new(PdfSettings::default()).unwrap();
+        assert!(matches!(
+            builder.add_image_bytes(b"not an image"),
+            Err(AppError::UnsupportedFormat(_))
+        ));
+        assert_eq!(builder.page_count(), 0);
+    }
+
+    #[test]
+    fn test_builder_rotates_jpeg_on_the_page() {
+        let jpeg = crate::utils::orientation::tests::oriented_jpeg(40, 20, 6);
+        let mut builder = PdfBuilder::new(PdfSettings::default()).unwrap();
+        builder.add_image_bytes(&jpeg).unwrap();
+
+        let doc = builder.document();
+        let page_id = builder.page_ids[0];
+        let content = Content::decode(&doc.get_page_content(page_id).unwrap()).unwrap();
+        let cm: Vec<f32> = content
+            .operations
+            .iter()
+            .find(|operation| operation.operator == "cm")
+            .unwrap()
+            .operands
+            .iter()
+            .map(|operand| operand.as_float().unwrap())
+            .collect();
+
+        // Upright image is 20x40, fitted to A4 height; the quarter turn swaps the axes
+        assert_eq!(cm[0], 0.0);
+        assert_eq!(cm[3], 0.0);
+        assert!((cm[1] + 842.0).abs() < 0.01);
+        assert!((cm[2] - 421.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_builder_keeps_jpeg_colour_when_optimizing() {
+        use crate::utils::jpeg::tests::{cmyk_jpeg, encode_jpeg, insert_segment};
+
+        let cmyk = cmyk_jpeg();
+        assert_eq!(image::load_from_memory(&cmyk).unwrap().width(), 8);
+        let mut icc = b"ICC_PROFILE\0\x01\x01".to_vec();
+        icc.extend_from_slice(&[7; 16]);
+        let tagged = insert_segment(&encode_jpeg(image::DynamicImage::new_rgb8(8, 8)), 0xE2, &icc);
+
+        let mut builder = PdfBuilder::new(PdfSettings::default()).unwrap();
+        builder.add_image_bytes(&cmyk).unwrap().add_image_bytes(&tagged).unwrap();
+
+        let doc = builder.document();
+        let color_spaces: Vec<Object> = builder
+            .page_ids
+            .iter()
+            .map(|&page_id| {
+                let resources = doc.get_dictionary(page_id).unwrap().get(b"Resources").unwrap();
+                let xobjects = resources.as_dict().unwrap().get(b"XObject").unwrap();
+                let image_id = xobjects.as_dict().unwrap().get(b"Im1").unwrap();
+                let image = doc.get_object(image_id.as_reference().unwrap()).unwrap();
+                image.as_stream().unwrap().dict.get(b"ColorSpace").unwrap().clone()
+            })
+            .collect();
+
+        assert_eq!(color_spaces[0].as_name().unwrap(), b"DeviceCMYK");
+        let icc_based = color_spaces[1].as_array().unwrap();
+        assert_eq!(icc_based[0].as_name().unwrap(), b"ICCBased");
+        let profile = doc.get_object(icc_based[1].as_reference().unwrap()).unwrap();
+        assert_eq!(profile.as_stream().unwrap().content, vec![7; 16]);
+    }
+
+    #[test]
+    fn test_builder_sizes_page_to_scan_resolution() {
+        let mut exif = b"Exif\0\0".to_vec();
+        exif.extend_from_slice(&crate::utils::dpi::tests::tiff_resolution(300, 2));
+        let mut jpeg = Vec::new();
+        image::DynamicImage::new_rgb8(600, 300)
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xFF, 0xE1]);
+        data.extend_from_slice(&((exif.len() + 2) as u16).to_be_bytes());
+        data.extend_from_slice(&exif);
+        data.extend_from_slice(&jpeg[2..]);
+
+        let settings = PdfSettings {
+            page_size: crate::settings::PageSize::MatchImage,
+            optimize_images: Some(false),
+            ..Default::default()
+        };
+        let mut builder = PdfBuilder::new(settings).unwrap();
+        builder.add_image_bytes(&data).unwrap();
+
+        // 600x300 pixels at 300 DPI is 2x1 inches
+        assert_eq!(media_box(&builder, 0), vec![0.0, 0.0, 144.0, 72.0]);
+    }
+
+    #[test]
+    fn test_builder_draws_svg_as_vector_form() {
+        let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 20 10">
+            <rect width="20" height="10" fill="red"/>
+        </svg>"#;
+        let settings = PdfSettings {
+            page_size: crate::settings::PageSize::MatchImage,
+            ..Default::default()
+        };
+        let mut builder = PdfBuilder::new(settings).unwrap();
+        builder.add_image_bytes(svg).unwrap();
+
+        // 200x100 CSS pixels at 96 per inch
+        assert_eq!(media_box(&builder, 0), vec![0.0, 0.0, 150.0, 75.0]);
+
+        let mut output = Vec::new();
+        builder.write_to(&mut output).unwrap();
+        let doc = Document::load_mem(&output).unwrap();
+        let page_id = doc.page_iter().next().unwrap();
+        let (resources, _) = doc.get_page_resources(page_id).unwrap();
+        let xobjects = resources.unwrap().get(b"XObject").unwrap();
+        let form_id = xobjects.as_dict().unwrap().get(b"Im1").unwrap();
+        let form_id = form_id.as_reference().unwrap();
+        let form = doc.get_object(form_id).unwrap().as_stream().unwrap();
+        assert_eq!(form.dict.get(b"Subtype").unwrap().as_name().unwrap(), b"Form");
+        let drawing = Content::decode(&form.decompressed_content().unwrap()).unwrap();
+        assert!(drawing.operations.iter().any(|operation| operation.operator == "f"));
+
+        // The drawing is fitted to a fixed page like an image
+        let mut builder = PdfBuilder::new(PdfSettings::default()).unwrap();
+        builder.add_image_bytes(svg).unwrap();
+        let doc = builder.document();
+        let content = Content::decode(&doc.get_page_content(builder.page_ids[0]).unwrap()).unwrap();
+        let cm = content
+            .operations
+            .iter()
+            .find(|operation| operation.operator == "cm")
+            .unwrap();
+        assert_eq!(cm.operands[0].as_float().unwrap(), 595.0);
+        assert_eq!(cm.operands[3].as_float().unwrap(), 297.5);
+
+        // Converted drawings are not checked against PDF/A
+        let settings = PdfSettings {
+            compliance: Some(Compliance::PdfA2b),
+            ..Default::default()
+        };
+        assert!(matches!(
+            PdfBuilder::new(settings).unwrap().add_image_bytes(svg),
+            Err(AppError::NotCompliant(_))
+        ));
+    }
+
+    #[test]
+    fn test_builder_merges_pdf_pages() {
+        let match_image = || PdfSettings {
+            page_size: crate::settings::PageSize::MatchImage,
+            ..Default::default()
+        };
+        // Pages 10, 20 and 30 points wide
+        let mut source = PdfBuilder::new(match_image()).unwrap();
+        for width in [10, 20, 30] {
+            source
+                .add_image_bytes(&png_bytes(image::DynamicImage::new_rgb8(width, 10)))
+                .unwrap();
+        }
+        let path = std::env::temp_dir().join("test_builder_merge_source.pdf");
+        source.save(&path).unwrap();
+        let path = path.to_string_lossy().to_string();
+
+        let settings = PdfSettings {
+            page_ranges: Some(HashMap::from([(path.clone(), "3, 1".to_string())])),
+            ..match_image()
+        };
+        let png = png_bytes(image::DynamicImage::new_rgb8(40, 10));
+        let mut output = Vec::new();
+        PdfBuilder::new(settings)
+            .unwrap()
+            .add_image_bytes(&png)
+            .unwrap()
+            .add_image(&path)
+            .unwrap()
+            .add_image_bytes(&png)
+            .unwrap()
+            .write_to(&mut output)
+            .unwrap();
+
+        let doc = Document::load_mem(&output).unwrap();
+        let widths: Vec<f32> = doc
+            .page_iter()
+            .map(|page_id| {
+                let page = doc.get_dictionary(page_id).unwrap();
+                crate::import::page_box(&doc, page).unwrap()[2]
+            })
+            .collect();
+        assert_eq!(widths, [40.0, 30.0, 10.0, 40.0]);
+        // Copied pages bring their images along
+        let copied = doc.page_iter().nth(1).unwrap();
+        let (resources, _) = doc.get_page_resources(copied).unwrap();
+        assert!(resources.unwrap().has(b"XObject"));
+
+        // Arbitrary PDF content cannot be vouched for in PDF/A output
+        let settings = PdfSettings {
+            compliance: Some(Compliance::PdfA2b),
+            ..Default::default()
+        };
+        assert!(matches!(
+            PdfBuilder::new(settings).unwrap().add_image_bytes(&output),
+            Err(AppError::NotCompliant(_))
+        ));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_builder_appends_incremental_update() {
+        let path = std::env::temp_dir().join("test_builder_append_archive.pdf");
+        let widths = |data: &[u8]| -> Vec<f32> {
+            let doc = Document::load_mem(data).unwrap();
+            doc.page_iter()
+                .map(|page_id| {
+                    let page = doc.get_dictionary(page_id).unwrap();
+                    crate::import::page_box(&doc, page).unwrap()[2]
+                })
+                .collect()
+        };
+        let append = |original: &[u8], index: Option<usize>, image_widths: &[u32]| {
+            std::fs::write(&path, original).unwrap();
+            let settings = PdfSettings {
+                page_size: PageSize::MatchImage,
+                object_streams: Some(true),
+                append_to: Some(crate::settings::AppendTo {
+                    path: path.to_string_lossy().to_string(),
+                    index,
+                }),
+                ..Default::default()
+            };
+            let output = SharedBuffer::default();
+            let mut builder = PdfBuilder::stream_to(settings, output.clone()).unwrap();
+            for &width in image_widths {
+                builder
+                    .add_image_bytes(&png_bytes(image::DynamicImage::new_rgb8(width, 10)))
+                    .unwrap();
+            }
+            assert_eq!(builder.page_count(), image_widths.len());
+            builder.finish().unwrap();
+            let appended = output.0.lock().unwrap().clone();
+            appended
+        };
+
+        // Files with a cross-reference table and with a cross-reference stream
+        for object_streams in [false, true] {
+            let settings = PdfSettings {
+                page_size: PageSize::MatchImage,
+                object_streams: Some(object_streams),
+                metadata: Some(crate::settings::DocumentMetadata {
+                    title: Some("Archive".to_string()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            let output = SharedBuffer::default();
+            let mut builder = PdfBuilder::stream_to(settings, output.clone()).unwrap();
+            for width in [10, 20] {
+                builder
+                    .add_image_bytes(&png_bytes(image::DynamicImage::new_rgb8(width, 10)))
+                    .unwrap();
+            }
+            builder.finish().unwrap();
+            let original = output.0.lock().unwrap().clone();
+
+            let updated = append(&original, Some(1), &[30, 40]);
+            assert!(updated.starts_with(&original));
+            let update = &updated[original.len()..];
+            let contains = |needle: &[u8]| update.windows(needle.len()).any(|w| w == needle);
+            // The update packs objects only into a file that already uses a
+            // cross-reference stream
+            assert_eq!(contains(b"/Type /ObjStm"), object_streams);
+            assert_eq!(contains(b"\nxref\n"), !object_streams);
+            assert!(!contains(b"/Type /Catalog"));
+            assert_eq!(widths(&updated), [10.0, 30.0, 40.0, 20.0]);
+
+            let doc = Document::load_mem(&updated).unwrap();
+            let info_id = doc.trailer.get(b"Info").and_then(Object::as_reference).unwrap();
+            let title = doc.get_dictionary(info_id).unwrap().get(b"Title").unwrap();
+            assert_eq!(lopdf::decode_text_string(title).unwrap(), "Archive");
+
+            // Updates stack, each pointing back to the one before
+            let rolled = append(&updated, None, &[50]);
+            assert!(rolled.starts_with(&updated));
+            assert_eq!(widths(&rolled), [10.0, 30.0, 40.0, 20.0, 50.0]);
+        }
+
+        let append_to = Some(crate::settings::AppendTo {
+            path: path.to_string_lossy().to_string(),
+            index: None,
+        });
+        let mut builder = PdfBuilder::new(PdfSettings {
+            append_to: append_to.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(builder.write_to(Vec::new()).is_err());
+        let encrypted = PdfSettings {
+            append_to,
+            encryption: Some(Default::default()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            PdfBuilder::new(encrypted),
+            Err(AppError::InvalidSettings(_))
+        ));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_builder_auto_orientation_per_page() {
+        let settings = PdfSettings {
+            orientation: crate::settings::Orientation::Auto,
+            ..Default::default()
+        };
+        let mut builder = PdfBuilder::new(settings).unwrap();
+        builder
+            .add_image_bytes(&png_bytes(image::DynamicImage::new_rgb8(30, 20)))
+            .unwrap()
+            .add_image_bytes(&png_bytes(image::DynamicImage::new_rgb8(20, 30)))
+            .unwrap();
+
+        assert_eq!(media_box(&builder, 0), vec![0.0, 0.0, 842.0, 595.0]);
+        assert_eq!(media_box(&builder, 1), vec![0.0, 0.0, 595.0, 842.0]);
+    }
+
+    #[test]
+    fn test_builder_packs_images_into_grid() {
+        let settings = PdfSettings {
+            grid: Some(crate::settings::GridLayout {
+                rows: 2,
+                columns: 2,
+                gutter: 5.0,
+                unit: crate::settings::LengthUnit::Mm,
+                fill_order: crate::settings::FillOrder::RowMajor,
+            }),
+            ..Default::default()
+        };
+        let png = png_bytes(image::DynamicImage::new_rgb8(30, 20));
+        let mut builder = PdfBuilder::new(settings).unwrap();
+        for _ in 0..5 {
+            builder.add_image_bytes(&png).unwrap();
+        }
+        assert_eq!(builder.page_count(), 2);
+
+        let mut output = Vec::new();
+        builder.write_to(&mut output).unwrap();
+
+        let doc = Document::load_mem(&output).unwrap();
+        let pages: Vec<_> = doc.get_pages().into_values().collect();
+        let xobject_names = |page_id| {
+            let (resources, _) = doc.get_page_resources(page_id).unwrap();
+            let xobjects = resources.unwrap().get(b"XObject").unwrap().as_dict().unwrap();
+            xobjects.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(pages.len(), 2);
+        assert_eq!(xobject_names(pages[0]), [b"Im1", b"Im2", b"Im3", b"Im4"]);
+        assert_eq!(xobject_names(pages[1]), [b"Im1"]);
+    }
+
+    #[test]
+    fn test_builder_rejects_grid_with_match_image() {
+        let settings = PdfSettings {
+            page_size: crate::settings::PageSize::MatchImage,
+            grid: Some(crate::settings::GridLayout {
+                rows: 1,
+                columns: 2,
+                gutter: 0.0,
+                unit: crate::settings::LengthUnit::Pt,
+                fill_order: crate::settings::FillOrder::RowMajor,
+            }),
+            ..Default::default()
+        };
+        assert!(matches!(
+            PdfBuilder::new(settings),
+            Err(AppError::InvalidSettings(_))
+        ));
+    }
+
+    #[test]
+    fn test_builder_expands_frames() {
+        let gif = crate::utils::frames::tests::animated_gif(&[0, 100, 200]);
+        let tiff = crate::utils::frames::tests::multi_page_tiff(&[(4, 2), (6, 3)]);
+
+        let mut builder = PdfBuilder::new(PdfSettings::default()).unwrap();
+        builder.add_image_bytes(&gif).unwrap().add_image_bytes(&tiff).unwrap();
+        assert_eq!(builder.page_count(), 5);
+
+        // Each frame is handed over before the next one is decoded
+        let preparer = builder.preparer();
+        let input = preparer.read_bytes(&gif).unwrap();
+        assert_eq!(input.image_count(), 3);
+        let mut handed_over = 0;
+        let result = preparer.prepare_each(input, |_| {
+            handed_over += 1;
+            Err(AppError::Cancelled)
+        });
+        assert!(matches!(result, Err(AppError::Cancelled)));
+        assert_eq!(handed_over, 1);
+
+        let settings = PdfSettings {
+            frames: Some(FrameSelection::First),
+            ..Default::default()
+        };
+        let mut builder = PdfBuilder::new(settings).unwrap();
+        builder.add_image_bytes(&gif).unwrap().add_image_bytes(&tiff).unwrap();
+        assert_eq!(builder.page_count(), 2);
+
+        let settings = PdfSettings {
+            frames: Some(FrameSelection::Range { start: 2, end: 2 }),
+            ..Default::default()
+        };
+        let mut builder = PdfBuilder::new(settings).unwrap();
+        builder.add_image_bytes(&gif).unwrap().add_image_bytes(&tiff).unwrap();
+        assert_eq!(builder.page_count(), 2);
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_fallback_dpi() {
+        let settings = PdfSettings {
+            fallback_dpi: Some(0.0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            PdfBuilder::new(settings),
+            Err(AppError::InvalidSettings(_))
+        ));
+    }
+}
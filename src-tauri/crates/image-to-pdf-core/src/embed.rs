@@ -0,0 +1,334 @@
+use crate::error::{AppError, Result};
+use crate::settings::{PdfSettings, TransparencyMode};
+use crate::utils::jpeg::JpegInfo;
+use lopdf::{dictionary, Document, Object, Stream};
+
+// ============================================================================
+// Transparency Handling
+// ============================================================================
+
+/// Per-image embedding options resolved once from `PdfSettings`
+pub struct ImageOptions {
+    pub optimize: bool,
+    pub transparency: TransparencyMode,
+    pub background: [u8; 3],
+}
+
+impl ImageOptions {
+    pub fn from_settings(settings: &PdfSettings) -> Result<Self> {
+        Ok(ImageOptions {
+            optimize: settings.optimize_images.unwrap_or(true),
+            transparency: settings.transparency.unwrap_or(TransparencyMode::Preserve),
+            background: match &settings.background_color {
+                Some(color) => parse_hex_color(color)?,
+                None => [255, 255, 255],
+            },
+        })
+    }
+}
+
+/// Parse a `#RRGGBB` (or `RRGGBB`) colour string
+pub fn parse_hex_color(value: &str) -> Result<[u8; 3]> {
+    let hex = value.trim().trim_start_matches('#');
+
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AppError::InvalidSettings(format!(
+            "Invalid background color: {}",
+            value
+        )));
+    }
+
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+    Ok([channel(0), channel(2), channel(4)])
+}
+
+/// Blend an RGBA image onto a solid background colour
+pub fn flatten_alpha(img: &image::RgbaImage, background: [u8; 3]) -> image::RgbImage {
+    image::RgbImage::from_fn(img.width(), img.height(), |x, y| {
+        let [r, g, b, a] = img.get_pixel(x, y).0;
+        let alpha = a as u32;
+        let blend = |fg: u8, bg: u8| ((fg as u32 * alpha + bg as u32 * (255 - alpha) + 127) / 255) as u8;
+
+        image::Rgb([
+            blend(r, background[0]),
+            blend(g, background[1]),
+            blend(b, background[2]),
+        ])
+    })
+}
+
+/// Split an RGBA image into RGB samples and an alpha mask
+///
+/// The mask is `None` when every pixel is fully opaque.
+pub fn split_alpha(img: image::RgbaImage) -> (Vec<u8>, Option<Vec<u8>>) {
+    let pixel_count = (img.width() * img.height()) as usize;
+    let mut rgb = Vec::with_capacity(pixel_count * 3);
+    let mut alpha = Vec::with_capacity(pixel_count);
+
+    for pixel in img.pixels() {
+        rgb.extend_from_slice(&pixel.0[..3]);
+        alpha.push(pixel.0[3]);
+    }
+
+    let mask = if alpha.iter().all(|&a| a == u8::MAX) {
+        None
+    } else {
+        Some(alpha)
+    };
+
+    (rgb, mask)
+}
+
+/// Compress raw sample data with zlib for a `FlateDecode` stream
+pub fn compress_flate(data: &[u8]) -> Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder
+        .write_all(data)
+        .map_err(|e| AppError::ImageProcessingError(format!("Compression failed: {}", e)))?;
+    encoder
+        .finish()
+        .map_err(|e| AppError::ImageProcessingError(format!("Compression failed: {}", e)))
+}
+
+/// Flate-compressed samples of a decoded image, ready to embed
+///
+/// Encoding needs no access to the document, so it can run on any thread.
+pub struct EncodedRaster {
+    pub width: u32,
+    pub height: u32,
+    /// Compressed `DeviceRGB` samples
+    pub color: Vec<u8>,
+    /// Compressed `DeviceGray` alpha mask, when transparency is kept
+    pub mask: Option<Vec<u8>>,
+    /// RGB ICC profile the samples are in, instead of plain `DeviceRGB`
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+/// Compress a decoded image, keeping or flattening its alpha channel
+pub fn encode_raster(
+    img: &image::DynamicImage,
+    transparency: TransparencyMode,
+    background: [u8; 3],
+) -> Result<EncodedRaster> {
+    let (width, height) = (img.width(), img.height());
+    let encoded = |color: &[u8], mask: Option<&[u8]>| -> Result<EncodedRaster> {
+        Ok(EncodedRaster {
+            width,
+            height,
+            color: compress_flate(color)?,
+            mask: mask.map(compress_flate).transpose()?,
+            icc_profile: None,
+        })
+    };
+
+    if !img.color().has_alpha() {
+        return encoded(&img.to_rgb8().into_raw(), None);
+    }
+
+    match transparency {
+        TransparencyMode::Preserve => {
+            let (rgb, mask) = split_alpha(img.to_rgba8());
+            encoded(&rgb, mask.as_deref())
+        }
+        TransparencyMode::Flatten => {
+            let flattened = flatten_alpha(&img.to_rgba8(), background);
+            encoded(&flattened.into_raw(), None)
+        }
+    }
+}
+
+/// Add a Flate-compressed image XObject with the given colour space
+fn add_flate_image(
+    doc: &mut Document,
+    width: u32,
+    height: u32,
+    color_space: Object,
+    compressed_data: Vec<u8>,
+    smask: Option<(u32, u16)>,
+) -> (u32, u16) {
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => width,
+        "Height" => height,
+        "ColorSpace" => color_space,
+        "BitsPerComponent" => 8,
+        "Filter" => "FlateDecode",
+        "Length" => compressed_data.len() as i64,
+    };
+    if let Some(smask_id) = smask {
+        dict.set("SMask", smask_id);
+    }
+
+    doc.add_object(Stream::new(dict, compressed_data))
+}
+
+/// Embed an encoded image, with its alpha mask as an `/SMask`
+pub fn add_encoded_raster(doc: &mut Document, raster: EncodedRaster) -> (u32, u16) {
+    let (width, height) = (raster.width, raster.height);
+    let smask_id = raster
+        .mask
+        .map(|alpha| add_flate_image(doc, width, height, "DeviceGray".into(), alpha, None));
+    let color_space = match raster.icc_profile {
+        Some(profile) => add_icc_color_space(doc, profile, 3, "DeviceRGB"),
+        None => "DeviceRGB".into(),
+    };
+
+    add_flate_image(doc, width, height, color_space, raster.color, smask_id)
+}
+
+/// Add an ICC profile stream and return an `ICCBased` colour space using it
+fn add_icc_color_space(
+    doc: &mut Document,
+    profile: Vec<u8>,
+    components: u8,
+    alternate: &str,
+) -> Object {
+    let icc_id = doc.add_object(Stream::new(
+        dictionary! {
+            "N" => components as i64,
+            "Alternate" => alternate,
+            "Length" => profile.len() as i64,
+        },
+        profile,
+    ));
+    vec!["ICCBased".into(), icc_id.into()].into()
+}
+
+// ============================================================================
+// JPEG Passthrough
+// ============================================================================
+
+/// Embed JPEG data as-is with a colour space taken from its header
+pub fn add_jpeg_image(doc: &mut Document, info: &JpegInfo, jpeg_data: Vec<u8>) -> (u32, u16) {
+    let device_space = info.device_color_space();
+
+    // Carry an embedded ICC profile over as an ICCBased colour space
+    let color_space: Object = match &info.icc_profile {
+        Some(profile) => add_icc_color_space(doc, profile.clone(), info.components, device_space),
+        None => device_space.into(),
+    };
+
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => info.width,
+        "Height" => info.height,
+        "ColorSpace" => color_space,
+        "BitsPerComponent" => info.bits_per_component as i64,
+        "Filter" => "DCTDecode",
+        "Length" => jpeg_data.len() as i64,
+    };
+
+    // Adobe CMYK JPEGs store inverted samples
+    if info.is_inverted_cmyk() {
+        dict.set(
+            "Decode",
+            vec![1.into(), 0.into(), 1.into(), 0.into(), 1.into(), 0.into(), 1.into(), 0.into()],
+        );
+    }
+
+    doc.add_object(Stream::new(dict, jpeg_data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#FF8000").unwrap(), [255, 128, 0]);
+        assert_eq!(parse_hex_color("00ff00").unwrap(), [0, 255, 0]);
+        assert!(parse_hex_color("#FFF").is_err());
+        assert!(parse_hex_color("#GGGGGG").is_err());
+    }
+
+    #[test]
+    fn test_flatten_alpha_onto_background() {
+        let img = image::RgbaImage::from_fn(2, 1, |x, _| {
+            if x == 0 {
+                image::Rgba([0, 0, 0, 0])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+
+        let flattened = flatten_alpha(&img, [255, 0, 0]);
+        assert_eq!(flattened.get_pixel(0, 0).0, [255, 0, 0]);
+        assert_eq!(flattened.get_pixel(1, 0).0, [0, 0, 255]);
+    }
+
+    #[test]
+    fn test_split_alpha_opaque_has_no_mask() {
+        let img = image::RgbaImage::from_pixel(2, 2, image::Rgba([10, 20, 30, 255]));
+        let (rgb, mask) = split_alpha(img);
+        assert_eq!(rgb.len(), 12);
+        assert!(mask.is_none());
+    }
+
+    #[test]
+    fn test_encoded_raster_preserve_creates_smask() {
+        let img = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            4,
+            4,
+            image::Rgba([10, 20, 30, 128]),
+        ));
+        let mut doc = Document::with_version("1.5");
+
+        let raster = encode_raster(&img, TransparencyMode::Preserve, [255, 255, 255]).unwrap();
+        let image_id = add_encoded_raster(&mut doc, raster);
+        let image_dict = doc.get_object(image_id).unwrap().as_stream().unwrap().dict.clone();
+        let smask_id = image_dict.get(b"SMask").unwrap().as_reference().unwrap();
+        let smask = doc.get_object(smask_id).unwrap().as_stream().unwrap();
+        assert_eq!(smask.dict.get(b"ColorSpace").unwrap().as_name().unwrap(), b"DeviceGray");
+
+        let raster = encode_raster(&img, TransparencyMode::Flatten, [255, 255, 255]).unwrap();
+        let image_id = add_encoded_raster(&mut doc, raster);
+        let image_dict = &doc.get_object(image_id).unwrap().as_stream().unwrap().dict;
+        assert!(image_dict.get(b"SMask").is_err());
+    }
+
+    #[test]
+    fn test_encoded_raster_with_icc_profile() {
+        let img = image::DynamicImage::ImageRgb8(image::RgbImage::new(2, 2));
+        let mut doc = Document::with_version("1.5");
+
+        let mut raster = encode_raster(&img, TransparencyMode::Preserve, [255, 255, 255]).unwrap();
+        raster.icc_profile = Some(vec![0u8; 16]);
+        let image_id = add_encoded_raster(&mut doc, raster);
+        let image_dict = &doc.get_object(image_id).unwrap().as_stream().unwrap().dict;
+        let color_space = image_dict.get(b"ColorSpace").unwrap().as_array().unwrap();
+        assert_eq!(color_space[0].as_name().unwrap(), b"ICCBased");
+        let icc = doc.get_object(color_space[1].as_reference().unwrap()).unwrap();
+        let icc_dict = &icc.as_stream().unwrap().dict;
+        assert_eq!(icc_dict.get(b"N").unwrap().as_i64().unwrap(), 3);
+        assert_eq!(icc_dict.get(b"Alternate").unwrap().as_name().unwrap(), b"DeviceRGB");
+    }
+
+    #[test]
+    fn test_add_jpeg_image_cmyk_with_icc() {
+        let info = JpegInfo {
+            width: 8,
+            height: 8,
+            components: 4,
+            bits_per_component: 8,
+            adobe_transform: Some(0),
+            icc_profile: Some(vec![0u8; 16]),
+            dpi: None,
+        };
+        let mut doc = Document::with_version("1.5");
+
+        let image_id = add_jpeg_image(&mut doc, &info, vec![0xFF, 0xD8]);
+        let dict = &doc.get_object(image_id).unwrap().as_stream().unwrap().dict;
+
+        let color_space = dict.get(b"ColorSpace").unwrap().as_array().unwrap();
+        assert_eq!(color_space[0].as_name().unwrap(), b"ICCBased");
+        let icc = doc.get_object(color_space[1].as_reference().unwrap()).unwrap();
+        let icc_dict = &icc.as_stream().unwrap().dict;
+        assert_eq!(icc_dict.get(b"N").unwrap().as_i64().unwrap(), 4);
+        assert_eq!(icc_dict.get(b"Alternate").unwrap().as_name().unwrap(), b"DeviceCMYK");
+        assert_eq!(dict.get(b"Decode").unwrap().as_array().unwrap().len(), 8);
+    }
+}
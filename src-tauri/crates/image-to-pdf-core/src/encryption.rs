@@ -0,0 +1,127 @@
+use crate::error::{AppError, Result};
+use crate::settings::{Encryption, EncryptionAlgorithm, Permissions};
+use lopdf::encryption::crypt_filters::{Aes128CryptFilter, Aes256CryptFilter, CryptFilter};
+use lopdf::{Document, EncryptionState, EncryptionVersion, Object, StringFormat};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Name of the single crypt filter used for both strings and streams
+const CRYPT_FILTER: &[u8] = b"StdCF";
+
+/// Random bytes from the operating system
+pub fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    getrandom::fill(&mut bytes)
+        .map_err(|e| AppError::PdfGenerationError(format!("No random source available: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Give the document a trailer `/ID` unless it already has one
+pub fn ensure_file_id(doc: &mut Document) -> Result<()> {
+    if doc.trailer.get(b"ID").is_err() {
+        let id = Object::String(random_bytes::<16>()?.to_vec(), StringFormat::Hexadecimal);
+        doc.trailer.set("ID", vec![id.clone(), id]);
+    }
+    Ok(())
+}
+
+/// Standard security handler state for the settings.
+///
+/// AES-128 keys are derived from the trailer `/ID`, so call
+/// [`ensure_file_id`] first.
+pub fn encryption_state(doc: &Document, encryption: &Encryption) -> Result<EncryptionState> {
+    let owner_password = match &encryption.owner_password {
+        Some(password) if !password.is_empty() => password.clone(),
+        _ => random_bytes::<16>()?
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    };
+    let user_password = encryption.user_password.as_str();
+    let permissions = permission_flags(encryption.permissions);
+    let file_encryption_key = random_bytes::<32>()?;
+
+    let version = match encryption.algorithm {
+        EncryptionAlgorithm::Aes128 => EncryptionVersion::V4 {
+            document: doc,
+            encrypt_metadata: true,
+            crypt_filters: crypt_filters(Arc::new(Aes128CryptFilter)),
+            stream_filter: CRYPT_FILTER.to_vec(),
+            string_filter: CRYPT_FILTER.to_vec(),
+            owner_password: &owner_password,
+            user_password,
+            permissions,
+        },
+        EncryptionAlgorithm::Aes256 => EncryptionVersion::V5 {
+            encrypt_metadata: true,
+            crypt_filters: crypt_filters(Arc::new(Aes256CryptFilter)),
+            file_encryption_key: &file_encryption_key,
+            stream_filter: CRYPT_FILTER.to_vec(),
+            string_filter: CRYPT_FILTER.to_vec(),
+            owner_password: &owner_password,
+            user_password,
+            permissions,
+        },
+    };
+
+    EncryptionState::try_from(version).map_err(|e| {
+        AppError::InvalidSettings(format!("Cannot encrypt with these passwords: {}", e))
+    })
+}
+
+fn crypt_filters(filter: Arc<dyn CryptFilter>) -> BTreeMap<Vec<u8>, Arc<dyn CryptFilter>> {
+    BTreeMap::from([(CRYPT_FILTER.to_vec(), filter)])
+}
+
+/// Map the permission switches to `/P` bits. Text extraction for
+/// accessibility tools is always allowed.
+fn permission_flags(permissions: Permissions) -> lopdf::Permissions {
+    let mut flags = lopdf::Permissions::COPYABLE_FOR_ACCESSIBILITY;
+
+    if permissions.print {
+        flags |= lopdf::Permissions::PRINTABLE | lopdf::Permissions::PRINTABLE_IN_HIGH_QUALITY;
+    }
+    if permissions.copy {
+        flags |= lopdf::Permissions::COPYABLE;
+    }
+    if permissions.modify {
+        flags |= lopdf::Permissions::MODIFIABLE
+            | lopdf::Permissions::ANNOTABLE
+            | lopdf::Permissions::FILLABLE
+            | lopdf::Permissions::ASSEMBLABLE;
+    }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_flags() {
+        let flags = permission_flags(Permissions {
+            print: true,
+            copy: false,
+            modify: false,
+        });
+        assert!(flags.contains(lopdf::Permissions::PRINTABLE));
+        assert!(!flags.contains(lopdf::Permissions::COPYABLE));
+        assert!(!flags.contains(lopdf::Permissions::MODIFIABLE));
+        assert!(flags.contains(lopdf::Permissions::COPYABLE_FOR_ACCESSIBILITY));
+
+        assert_eq!(
+            permission_flags(Permissions::default()),
+            lopdf::Permissions::all()
+        );
+    }
+
+    #[test]
+    fn test_file_id_is_kept() {
+        let mut doc = Document::with_version("1.7");
+        ensure_file_id(&mut doc).unwrap();
+        let id = doc.trailer.get(b"ID").unwrap().clone();
+        ensure_file_id(&mut doc).unwrap();
+        assert_eq!(doc.trailer.get(b"ID").unwrap(), &id);
+        assert_eq!(id.as_array().unwrap()[0].as_str().unwrap().len(), 16);
+    }
+}
@@ -0,0 +1,100 @@
+use serde::Serialize;
+use thiserror::Error;
+
+/// Application errors that can be returned to the frontend
+#[derive(Debug, Error, Serialize)]
+#[serde(tag = "type", content = "message")]
+pub enum AppError {
+    #[error("Unsupported image format: {0}")]
+    UnsupportedFormat(String),
+
+    #[error("File extension does not match its content: {0}")]
+    FormatMismatch(String),
+
+    #[error("Image file not found: {0}")]
+    ImageNotFound(String),
+
+    #[error("Failed to read image: {0}")]
+    ImageReadError(String),
+
+    #[error("Image processing error: {0}")]
+    ImageProcessingError(String),
+
+    #[error("Image file too large: {0} bytes (max 50 MB)")]
+    ImageTooLarge(u64),
+
+    #[error("Failed to generate PDF: {0}")]
+    PdfGenerationError(String),
+
+    #[error("IO error: {0}")]
+    IoError(String),
+
+    #[error("Invalid custom page dimensions")]
+    InvalidDimensions,
+
+    #[error("Invalid settings: {0}")]
+    InvalidSettings(String),
+
+    #[error("Cannot produce PDF/A output: {0}")]
+    NotCompliant(String),
+
+    #[error("No images provided")]
+    NoImages,
+
+    #[error("Generation cancelled")]
+    Cancelled,
+}
+
+impl AppError {
+    /// Name of the variant, as serialized in the `type` field
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::UnsupportedFormat(_) => "UnsupportedFormat",
+            AppError::FormatMismatch(_) => "FormatMismatch",
+            AppError::ImageNotFound(_) => "ImageNotFound",
+            AppError::ImageReadError(_) => "ImageReadError",
+            AppError::ImageProcessingError(_) => "ImageProcessingError",
+            AppError::ImageTooLarge(_) => "ImageTooLarge",
+            AppError::PdfGenerationError(_) => "PdfGenerationError",
+            AppError::IoError(_) => "IoError",
+            AppError::InvalidDimensions => "InvalidDimensions",
+            AppError::InvalidSettings(_) => "InvalidSettings",
+            AppError::NotCompliant(_) => "NotCompliant",
+            AppError::NoImages => "NoImages",
+            AppError::Cancelled => "Cancelled",
+        }
+    }
+
+    /// Process exit code for the command-line binary (BSD `sysexits` values)
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            AppError::InvalidDimensions | AppError::InvalidSettings(_) | AppError::NoImages => 64,
+            AppError::UnsupportedFormat(_)
+            | AppError::FormatMismatch(_)
+            | AppError::ImageReadError(_)
+            | AppError::ImageTooLarge(_)
+            | AppError::NotCompliant(_) => 65,
+            AppError::ImageNotFound(_) => 66,
+            AppError::ImageProcessingError(_) | AppError::PdfGenerationError(_) => 70,
+            AppError::IoError(_) => 74,
+            AppError::Cancelled => 130,
+        }
+    }
+}
+
+// Convert std::io::Error to AppError
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        AppError::IoError(err.to_string())
+    }
+}
+
+// Convert image::ImageError to AppError
+impl From<image::ImageError> for AppError {
+    fn from(err: image::ImageError) -> Self {
+        AppError::ImageReadError(err.to_string())
+    }
+}
+
+/// Result type for Tauri commands
+pub type Result<T> = std::result::Result<T, AppError>;
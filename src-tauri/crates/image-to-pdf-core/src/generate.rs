@@ -0,0 +1,848 @@
+use crate::builder::PdfBuilder;
+use crate::error::{AppError, Result};
+use crate::prepare::{canonical_path, ImageEncoding, InputFile, PreparedImage};
+use crate::settings::{ErrorMode, PdfSettings};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+/// Stage of a generation job reported in progress events
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ProgressStage {
+    /// An image is about to be loaded and embedded
+    Processing,
+    /// An image has been embedded as a page
+    Embedded,
+    /// An image could not be added and was skipped or replaced by a placeholder
+    Failed,
+    /// All pages are done and the file is being written
+    Saving,
+    /// The output file is complete
+    Finished,
+}
+
+/// Progress update emitted while generating a PDF
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationProgress {
+    /// Zero-based index of the current image
+    pub index: usize,
+    pub total: usize,
+    pub path: String,
+    pub stage: ProgressStage,
+    /// Bytes written to the output so far, or the final file size once `Finished`
+    pub bytes_written: u64,
+    /// Why the image could not be added, at the `Failed` stage
+    pub failure: Option<ImageFailure>,
+}
+
+/// Summary of a finished generation job
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationStats {
+    /// Pages added, not counting those of a PDF appended to
+    pub page_count: usize,
+    /// Size of the finished PDF
+    pub file_size: u64,
+    /// Wall-clock time of the whole job, in milliseconds
+    pub elapsed_ms: u64,
+    /// Images that reused an identical image already embedded in the file
+    pub duplicates_collapsed: usize,
+    /// Sizes and encoding of every image added, in input order
+    pub images: Vec<ImageStats>,
+    /// Bytes removed by PNG/JPEG optimization over all added images
+    pub bytes_saved: u64,
+    /// Image data that repeated images did not add a second time
+    pub duplicate_bytes_saved: u64,
+    /// Images left out or replaced by a placeholder, in input order
+    pub failures: Vec<ImageFailure>,
+}
+
+/// How one input image ended up in the PDF
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageStats {
+    /// Zero-based index of the image in the input list
+    pub index: usize,
+    pub path: String,
+    /// Size of the input file
+    pub original_bytes: u64,
+    /// Image data the file added to the PDF; 0 when it repeats an earlier image
+    pub embedded_bytes: u64,
+    /// Encoding of the embedded data, the same for every frame of a file
+    pub encoding: ImageEncoding,
+    /// Bytes removed by PNG/JPEG optimization before embedding
+    pub optimization_saved: u64,
+}
+
+/// An image that could not be added to the document
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageFailure {
+    /// Zero-based index of the image in the input list
+    pub index: usize,
+    pub path: String,
+    /// `AppError` variant, e.g. `ImageReadError`
+    pub kind: String,
+    pub message: String,
+    /// Frame of a multi-page or animated image that failed, counted from 1;
+    /// the frames before it stay in the document
+    pub frame: Option<usize>,
+}
+
+/// Generate a PDF file from image paths, one page per image
+///
+/// PDF inputs add copies of their pages, limited to the range given for
+/// their path in `settings.page_ranges`. Keys and inputs are compared as
+/// canonical paths, and a key naming no input is an error.
+///
+/// With `settings.append_to`, the pages are inserted into an existing PDF as
+/// an incremental update: `output_path` receives the file's bytes unchanged
+/// followed by the new pages, and may be the appended file itself.
+///
+/// Images are read, decoded and compressed in parallel on `settings.workers`
+/// threads and embedded in their original order. `on_progress` is called
+/// before and after every image. Setting `cancelled` aborts the job with
+/// `AppError::Cancelled`. Images that cannot be read, decoded or embedded
+/// abort the job unless `settings.on_error` says to skip them or add a
+/// placeholder page; those are listed in [`GenerationStats::failures`] and
+/// sent with their `Failed` progress event, so they are known even when the
+/// job fails later. Pages are streamed to a temporary file as they are
+/// built, so memory use stays bounded by one image per worker, and a failed
+/// or cancelled job never leaves a partial PDF at `output_path`.
+pub fn generate_pdf(
+    image_paths: Vec<String>,
+    output_path: String,
+    settings: PdfSettings,
+    on_progress: &dyn Fn(GenerationProgress),
+    cancelled: &AtomicBool,
+) -> Result<GenerationStats> {
+    let started = Instant::now();
+    if image_paths.is_empty() {
+        return Err(AppError::NoImages);
+    }
+    check_page_ranges(&image_paths, &settings)?;
+    let pool = worker_pool(settings.workers)?;
+
+    let total = image_paths.len();
+    let report = |index: usize, stage: ProgressStage, bytes_written: u64| {
+        on_progress(GenerationProgress {
+            index,
+            total,
+            path: image_paths[index].clone(),
+            stage,
+            bytes_written,
+            failure: None,
+        })
+    };
+    let report_failure = |failure: &ImageFailure, bytes_written: u64| {
+        on_progress(GenerationProgress {
+            index: failure.index,
+            total,
+            path: failure.path.clone(),
+            stage: ProgressStage::Failed,
+            bytes_written,
+            failure: Some(failure.clone()),
+        })
+    };
+
+    // Write to a temporary file first so a failed or cancelled job never
+    // leaves a partial PDF at the output path
+    let partial_path = PathBuf::from(format!("{}.part", output_path));
+    let written = write_pages(
+        &image_paths,
+        &partial_path,
+        settings,
+        &pool,
+        &report,
+        &report_failure,
+        cancelled,
+    )
+    .and_then(|stats| {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(AppError::Cancelled);
+        }
+        std::fs::rename(&partial_path, &output_path)?;
+        Ok(stats)
+    });
+
+    let mut stats = match written {
+        Ok(stats) => stats,
+        Err(e) => {
+            std::fs::remove_file(&partial_path).ok(); // Ignore cleanup errors
+            return Err(e);
+        }
+    };
+
+    stats.file_size = std::fs::metadata(&output_path)?.len();
+    stats.elapsed_ms = started.elapsed().as_millis() as u64;
+    report(total - 1, ProgressStage::Finished, stats.file_size);
+
+    Ok(stats)
+}
+
+/// Reject page ranges for files that are not inputs, which would otherwise
+/// be ignored and leave every page of the intended file copied
+fn check_page_ranges(image_paths: &[String], settings: &PdfSettings) -> Result<()> {
+    let inputs: HashSet<PathBuf> = image_paths.iter().map(canonical_path).collect();
+    let mut unmatched: Vec<&str> = settings
+        .page_ranges
+        .iter()
+        .flatten()
+        .map(|(path, _)| path.as_str())
+        .filter(|path| !inputs.contains(&canonical_path(path)))
+        .collect();
+    if unmatched.is_empty() {
+        return Ok(());
+    }
+
+    unmatched.sort_unstable();
+    Err(AppError::InvalidSettings(format!(
+        "Page ranges given for files that are not inputs: {}",
+        unmatched.join(", ")
+    )))
+}
+
+/// Thread pool for preparing images; `None` uses one thread per CPU core
+fn worker_pool(workers: Option<usize>) -> Result<rayon::ThreadPool> {
+    if workers == Some(0) {
+        return Err(AppError::InvalidSettings(
+            "Worker count must be at least 1".to_string(),
+        ));
+    }
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(workers.unwrap_or(0))
+        .build()
+        .map_err(|e| AppError::PdfGenerationError(format!("Cannot start worker threads: {}", e)))
+}
+
+/// An input file as a worker hands it over for embedding
+enum Prepared {
+    Images(Vec<PreparedImage>),
+    /// A multi-page or animated file, prepared frame by frame while embedding
+    Frames(InputFile),
+}
+
+/// Stream one page per image into `path`
+///
+/// The returned stats leave the file size and elapsed time to the caller.
+fn write_pages(
+    image_paths: &[String],
+    path: &Path,
+    settings: PdfSettings,
+    pool: &rayon::ThreadPool,
+    report: &dyn Fn(usize, ProgressStage, u64),
+    report_failure: &dyn Fn(&ImageFailure, u64),
+    cancelled: &AtomicBool,
+) -> Result<GenerationStats> {
+    let on_error = settings.on_error.unwrap_or_default();
+    let file = std::fs::File::create(path)?;
+    let mut builder = PdfBuilder::stream_to(settings, BufWriter::new(file))?;
+    let mut images = Vec::new();
+    let mut failures = Vec::new();
+    let mut first_error = None;
+
+    // Prepare one file per worker at a time, then embed the batch in its
+    // original order. Multi-frame files are only read by the workers; their
+    // frames are prepared one by one as they are embedded, so memory stays
+    // bounded by one image per worker
+    let batch_size = pool.current_num_threads();
+    let preparer = builder.preparer();
+    for (batch_index, batch) in image_paths.chunks(batch_size).enumerate() {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(AppError::Cancelled);
+        }
+        let first_index = batch_index * batch_size;
+        for index in first_index..first_index + batch.len() {
+            report(index, ProgressStage::Processing, builder.bytes_written());
+        }
+
+        let prepared: Vec<Result<(u64, Prepared)>> = pool.install(|| {
+            batch
+                .par_iter()
+                .map(|image_path| {
+                    if cancelled.load(Ordering::Relaxed) {
+                        return Err(AppError::Cancelled);
+                    }
+                    let path = Path::new(image_path);
+                    let input = preparer.read_file(path)?;
+                    let prepared = if input.image_count() > 1 {
+                        Prepared::Frames(input)
+                    } else {
+                        let mut images = Vec::with_capacity(1);
+                        preparer.prepare_each(input, |image| {
+                            images.push(image);
+                            Ok(())
+                        })?;
+                        Prepared::Images(images)
+                    };
+                    Ok((std::fs::metadata(path)?.len(), prepared))
+                })
+                .collect()
+        });
+
+        for (index, result) in (first_index..).zip(prepared) {
+            // Embedding can fail too, e.g. on a page size PDF/A does not allow
+            let mut failed_frame = None;
+            let embedded = result.and_then(|(original_bytes, prepared)| {
+                let mut encoding = None;
+                let mut embedded_bytes = 0;
+                let mut optimization_saved = 0;
+                let mut images_added = 0;
+                let mut add = |image: PreparedImage| {
+                    if cancelled.load(Ordering::Relaxed) {
+                        return Err(AppError::Cancelled);
+                    }
+                    encoding.get_or_insert(image.encoding);
+                    optimization_saved += image.optimization_saved;
+                    embedded_bytes += builder.add_prepared(image)?;
+                    images_added += 1;
+                    Ok(())
+                };
+                match prepared {
+                    Prepared::Images(images) => images.into_iter().try_for_each(&mut add)?,
+                    Prepared::Frames(input) => {
+                        // Pages already streamed cannot be taken back, so a
+                        // failing frame is recorded after the ones added
+                        let first_frame = input.first_frame();
+                        if let Err(e) = preparer.prepare_each(input, &mut add) {
+                            failed_frame = Some(first_frame + images_added + 1);
+                            return Err(e);
+                        }
+                    }
+                }
+                Ok((original_bytes, encoding, embedded_bytes, optimization_saved))
+            });
+            let error = match embedded {
+                Ok((original_bytes, encoding, embedded_bytes, optimization_saved)) => {
+                    if let Some(encoding) = encoding {
+                        images.push(ImageStats {
+                            index,
+                            path: image_paths[index].clone(),
+                            original_bytes,
+                            embedded_bytes,
+                            encoding,
+                            optimization_saved,
+                        });
+                    }
+                    report(index, ProgressStage::Embedded, builder.bytes_written());
+                    continue;
+                }
+                Err(AppError::Cancelled) => return Err(AppError::Cancelled),
+                Err(e) if on_error == ErrorMode::Abort => return Err(e),
+                Err(e) => e,
+            };
+
+            let image_path = &image_paths[index];
+            if on_error == ErrorMode::Placeholder {
+                let name = Path::new(image_path)
+                    .file_name()
+                    .map_or_else(|| image_path.clone(), |name| name.to_string_lossy().to_string());
+                builder.add_placeholder(&name, &error)?;
+            }
+            let failure = ImageFailure {
+                index,
+                path: image_path.clone(),
+                kind: error.kind().to_string(),
+                message: error.to_string(),
+                frame: failed_frame,
+            };
+            report_failure(&failure, builder.bytes_written());
+            failures.push(failure);
+            first_error.get_or_insert(error);
+        }
+    }
+
+    if cancelled.load(Ordering::Relaxed) {
+        return Err(AppError::Cancelled);
+    }
+    // Every image was skipped, so there is nothing worth saving
+    if builder.page_count() == 0 {
+        return Err(first_error.unwrap_or(AppError::NoImages));
+    }
+
+    report(image_paths.len() - 1, ProgressStage::Saving, builder.bytes_written());
+    let stats = GenerationStats {
+        page_count: builder.page_count(),
+        duplicates_collapsed: builder.duplicates_collapsed(),
+        bytes_saved: images.iter().map(|image| image.optimization_saved).sum(),
+        duplicate_bytes_saved: builder.duplicate_bytes_saved(),
+        images,
+        failures,
+        ..Default::default()
+    };
+
+    builder.finish()?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    fn create_test_png(name: &str) -> PathBuf {
+        let img = image::RgbImage::from_pixel(20, 10, image::Rgb([200, 100, 50]));
+        let path = std::env::temp_dir().join(name);
+        img.save(&path).unwrap();
+        path
+    }
+
+    fn test_settings() -> PdfSettings {
+        PdfSettings {
+            optimize_images: Some(false),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_generate_pdf_reports_progress() {
+        let input = create_test_png("test_generate_progress.png");
+        let output = std::env::temp_dir().join("test_generate_progress.pdf");
+        let events = Mutex::new(Vec::new());
+
+        let stats = generate_pdf(
+            vec![input.to_string_lossy().to_string()],
+            output.to_string_lossy().to_string(),
+            test_settings(),
+            &|progress| events.lock().unwrap().push(progress),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        assert_eq!(stats.page_count, 1);
+        assert_eq!(stats.duplicates_collapsed, 0);
+        assert!(stats.failures.is_empty());
+        assert_eq!(stats.file_size, std::fs::metadata(&output).unwrap().len());
+        assert_eq!(
+            stats.images,
+            vec![ImageStats {
+                index: 0,
+                path: input.to_string_lossy().to_string(),
+                original_bytes: std::fs::metadata(&input).unwrap().len(),
+                embedded_bytes: stats.images[0].embedded_bytes,
+                encoding: ImageEncoding::Flate,
+                optimization_saved: 0,
+            }]
+        );
+        assert!(stats.images[0].embedded_bytes > 0);
+        // Optimization is off in the test settings
+        assert_eq!(stats.bytes_saved, 0);
+
+        let events = events.lock().unwrap();
+        let stages: Vec<_> = events.iter().map(|e| e.stage).collect();
+        assert_eq!(
+            stages,
+            vec![
+                ProgressStage::Processing,
+                ProgressStage::Embedded,
+                ProgressStage::Saving,
+                ProgressStage::Finished
+            ]
+        );
+        // The image is on disk before the page tree is written
+        assert!(events[1].bytes_written > 0);
+        assert_eq!(events[3].bytes_written, std::fs::metadata(&output).unwrap().len());
+
+        std::fs::remove_file(input).ok();
+        std::fs::remove_file(output).ok();
+    }
+
+    #[test]
+    fn test_generate_pdf_counts_duplicates() {
+        let input = create_test_png("test_generate_duplicates.png");
+        let output = std::env::temp_dir().join("test_generate_duplicates.pdf");
+        let path = input.to_string_lossy().to_string();
+
+        let stats = generate_pdf(
+            vec![path.clone(), path.clone(), path],
+            output.to_string_lossy().to_string(),
+            test_settings(),
+            &|_| {},
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        assert_eq!(stats.page_count, 3);
+        assert_eq!(stats.duplicates_collapsed, 2);
+        let embedded: Vec<_> = stats.images.iter().map(|image| image.embedded_bytes).collect();
+        assert!(embedded[0] > 0);
+        assert_eq!(embedded[1..], [0, 0]);
+        assert_eq!(stats.duplicate_bytes_saved, 2 * embedded[0]);
+
+        std::fs::remove_file(input).ok();
+        std::fs::remove_file(output).ok();
+    }
+
+    #[test]
+    fn test_generate_pdf_reports_image_encodings() {
+        let png = create_test_png("test_generate_encodings.png");
+        // Re-encoding at quality 85 shrinks the first JPEG but not the second
+        let noise = image::RgbImage::from_fn(32, 32, |x, y| {
+            image::Rgb([(x * 37 + y * 11) as u8, (x * y) as u8, (x ^ y) as u8 * 8])
+        });
+        let jpeg = |name: &str, quality: u8| {
+            let path = std::env::temp_dir().join(name);
+            let file = std::fs::File::create(&path).unwrap();
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(file, quality);
+            noise.write_with_encoder(encoder).unwrap();
+            path
+        };
+        let jpegs = [
+            jpeg("test_generate_encodings_high.jpg", 100),
+            jpeg("test_generate_encodings_low.jpg", 20),
+        ];
+        let output = std::env::temp_dir().join("test_generate_encodings.pdf");
+        let paths = vec![
+            png.to_string_lossy().to_string(),
+            jpegs[0].to_string_lossy().to_string(),
+            jpegs[1].to_string_lossy().to_string(),
+        ];
+        let run = |optimize_images: bool| {
+            let settings = PdfSettings {
+                optimize_images: Some(optimize_images),
+                ..Default::default()
+            };
+            generate_pdf(
+                paths.clone(),
+                output.to_string_lossy().to_string(),
+                settings,
+                &|_| {},
+                &AtomicBool::new(false),
+            )
+            .unwrap()
+        };
+        let encodings = |stats: &GenerationStats| {
+            stats.images.iter().map(|image| image.encoding).collect::<Vec<_>>()
+        };
+
+        let stats = run(false);
+        assert_eq!(stats.bytes_saved, 0);
+        assert_eq!(
+            encodings(&stats),
+            vec![
+                ImageEncoding::Flate,
+                ImageEncoding::DctPassthrough,
+                ImageEncoding::DctPassthrough
+            ]
+        );
+        let stats = run(true);
+        assert_eq!(
+            encodings(&stats),
+            vec![
+                ImageEncoding::Flate,
+                ImageEncoding::DctReencoded,
+                ImageEncoding::DctPassthrough
+            ]
+        );
+        // Savings come from the optimization step alone, not from decoding
+        let [png_stats, reencoded, passthrough] = &stats.images[..] else {
+            panic!("expected three images");
+        };
+        assert!(reencoded.optimization_saved > 0);
+        assert_eq!(
+            reencoded.optimization_saved,
+            reencoded.original_bytes - reencoded.embedded_bytes
+        );
+        assert_eq!(passthrough.optimization_saved, 0);
+        assert_eq!(
+            stats.bytes_saved,
+            png_stats.optimization_saved + reencoded.optimization_saved
+        );
+
+        std::fs::remove_file(png).ok();
+        for jpeg in jpegs {
+            std::fs::remove_file(jpeg).ok();
+        }
+        std::fs::remove_file(output).ok();
+    }
+
+    #[test]
+    fn test_generate_pdf_keeps_page_order_in_parallel() {
+        let inputs: Vec<PathBuf> = (0..7u32)
+            .map(|i| {
+                let img = image::RgbImage::from_pixel(10 + i, 10, image::Rgb([0, 0, 0]));
+                let path = std::env::temp_dir().join(format!("test_generate_order_{}.png", i));
+                img.save(&path).unwrap();
+                path
+            })
+            .collect();
+        let output = std::env::temp_dir().join("test_generate_order.pdf");
+        let settings = PdfSettings {
+            page_size: crate::settings::PageSize::MatchImage,
+            workers: Some(3),
+            ..test_settings()
+        };
+
+        generate_pdf(
+            inputs.iter().map(|path| path.to_string_lossy().to_string()).collect(),
+            output.to_string_lossy().to_string(),
+            settings,
+            &|_| {},
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+
+        let doc = lopdf::Document::load(&output).unwrap();
+        let widths: Vec<f32> = doc
+            .page_iter()
+            .map(|page_id| {
+                let media_box = doc.get_dictionary(page_id).unwrap().get(b"MediaBox").unwrap();
+                media_box.as_array().unwrap()[2].as_float().unwrap()
+            })
+            .collect();
+        assert_eq!(widths, (10..17).map(|w| w as f32).collect::<Vec<_>>());
+
+        let zero_workers = PdfSettings {
+            workers: Some(0),
+            ..test_settings()
+        };
+        let result = generate_pdf(
+            vec![inputs[0].to_string_lossy().to_string()],
+            output.to_string_lossy().to_string(),
+            zero_workers,
+            &|_| {},
+            &AtomicBool::new(false),
+        );
+        assert!(matches!(result, Err(AppError::InvalidSettings(_))));
+
+        for input in inputs {
+            std::fs::remove_file(input).ok();
+        }
+        std::fs::remove_file(output).ok();
+    }
+
+    #[test]
+    fn test_generate_pdf_skips_or_replaces_broken_images() {
+        let input = create_test_png("test_generate_on_error.png");
+        let broken = std::env::temp_dir().join("test_generate_on_error_broken.png");
+        // A PNG signature with nothing after it
+        std::fs::write(&broken, b"\x89PNG\r\n\x1a\n").unwrap();
+        let output = std::env::temp_dir().join("test_generate_on_error.pdf");
+        let paths = vec![
+            input.to_string_lossy().to_string(),
+            broken.to_string_lossy().to_string(),
+            input.to_string_lossy().to_string(),
+        ];
+        let run = |on_error: Option<ErrorMode>, paths: Vec<String>| {
+            let settings = PdfSettings {
+                on_error,
+                ..test_settings()
+            };
+            generate_pdf(
+                paths,
+                output.to_string_lossy().to_string(),
+                settings,
+                &|_| {},
+                &AtomicBool::new(false),
+            )
+        };
+
+        let result = run(None, paths.clone());
+        assert!(matches!(result, Err(AppError::ImageReadError(_))));
+        assert!(!output.exists());
+
+        let stats = run(Some(ErrorMode::Skip), paths.clone()).unwrap();
+        assert_eq!(stats.page_count, 2);
+        assert_eq!(stats.failures.len(), 1);
+        assert_eq!(stats.failures[0].index, 1);
+        assert_eq!(stats.failures[0].path, paths[1]);
+        assert_eq!(stats.failures[0].kind, "ImageReadError");
+        assert_eq!(stats.failures[0].frame, None);
+
+        let stats = run(Some(ErrorMode::Placeholder), paths.clone()).unwrap();
+        assert_eq!(stats.page_count, 3);
+        assert_eq!(stats.failures.len(), 1);
+        let doc = lopdf::Document::load(&output).unwrap();
+        let pages: Vec<_> = doc.page_iter().collect();
+        let text = String::from_utf8_lossy(&doc.get_page_content(pages[1]).unwrap()).to_string();
+        assert!(text.contains("(test_generate_on_error_broken.png)"));
+        let resources = doc.get_dictionary(pages[1]).unwrap().get(b"Resources").unwrap();
+        assert!(resources.as_dict().unwrap().get(b"Font").is_ok());
+
+        // Nothing left to save
+        let result = run(Some(ErrorMode::Skip), vec![paths[1].clone()]);
+        assert!(matches!(result, Err(AppError::ImageReadError(_))));
+
+        // A 1 pt page decodes fine but fails the PDF/A size check when embedded;
+        // PDF/A has no placeholder pages, so only skipping applies
+        let tiny = std::env::temp_dir().join("test_generate_on_error_tiny.png");
+        image::RgbImage::new(1, 1).save(&tiny).unwrap();
+        let paths = vec![paths[0].clone(), tiny.to_string_lossy().to_string()];
+        let run_pdfa = |on_error: Option<ErrorMode>| {
+            let settings = PdfSettings {
+                on_error,
+                page_size: crate::settings::PageSize::MatchImage,
+                compliance: Some(crate::settings::Compliance::PdfA2b),
+                ..test_settings()
+            };
+            generate_pdf(
+                paths.clone(),
+                output.to_string_lossy().to_string(),
+                settings,
+                &|_| {},
+                &AtomicBool::new(false),
+            )
+        };
+
+        let result = run_pdfa(None);
+        assert!(matches!(result, Err(AppError::NotCompliant(_))));
+
+        let stats = run_pdfa(Some(ErrorMode::Skip)).unwrap();
+        assert_eq!(stats.page_count, 1);
+        assert_eq!(stats.images.len(), 1);
+        assert_eq!(stats.failures.len(), 1);
+        assert_eq!(stats.failures[0].index, 1);
+        assert_eq!(stats.failures[0].kind, "NotCompliant");
+        // The image that did not fit left no XObject behind
+        let doc = lopdf::Document::load(&output).unwrap();
+        let image_count = doc
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .filter(|stream| {
+                matches!(stream.dict.get(b"Subtype").and_then(|subtype| subtype.as_name()), Ok(b"Image"))
+            })
+            .count();
+        assert_eq!(image_count, 1);
+
+        // A page of a multi-page file fails after the pages before it were
+        // added; the failure names it and is also sent as progress
+        let pages = std::env::temp_dir().join("test_generate_on_error_pages.tiff");
+        let tiff = crate::utils::frames::tests::multi_page_tiff(&[(100, 100), (1, 1), (100, 100)]);
+        std::fs::write(&pages, tiff).unwrap();
+        let settings = PdfSettings {
+            on_error: Some(ErrorMode::Skip),
+            page_size: crate::settings::PageSize::MatchImage,
+            compliance: Some(crate::settings::Compliance::PdfA2b),
+            ..test_settings()
+        };
+        let reported = Mutex::new(Vec::new());
+        let stats = generate_pdf(
+            vec![paths[0].clone(), pages.to_string_lossy().to_string()],
+            output.to_string_lossy().to_string(),
+            settings,
+            &|progress| reported.lock().unwrap().extend(progress.failure),
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        assert_eq!(stats.page_count, 2);
+        assert_eq!(stats.failures.len(), 1);
+        assert_eq!(stats.failures[0].index, 1);
+        assert_eq!(stats.failures[0].frame, Some(2));
+        assert_eq!(reported.into_inner().unwrap(), stats.failures);
+
+        std::fs::remove_file(input).ok();
+        std::fs::remove_file(broken).ok();
+        std::fs::remove_file(tiny).ok();
+        std::fs::remove_file(pages).ok();
+        std::fs::remove_file(output).ok();
+    }
+
+    #[test]
+    fn test_generate_pdf_appends_in_place() {
+        let input = create_test_png("test_generate_append.png");
+        let output = std::env::temp_dir().join("test_generate_append.pdf");
+        let run = |settings: PdfSettings| {
+            generate_pdf(
+                vec![input.to_string_lossy().to_string()],
+                output.to_string_lossy().to_string(),
+                settings,
+                &|_| {},
+                &AtomicBool::new(false),
+            )
+        };
+
+        run(test_settings()).unwrap();
+        let original = std::fs::read(&output).unwrap();
+
+        let settings = PdfSettings {
+            append_to: Some(crate::settings::AppendTo {
+                path: output.to_string_lossy().to_string(),
+                index: Some(0),
+            }),
+            ..test_settings()
+        };
+        let stats = run(settings).unwrap();
+        assert_eq!(stats.page_count, 1);
+
+        let updated = std::fs::read(&output).unwrap();
+        assert!(updated.starts_with(&original));
+        assert_eq!(stats.file_size, updated.len() as u64);
+        let doc = lopdf::Document::load_mem(&updated).unwrap();
+        assert_eq!(doc.get_pages().len(), 2);
+
+        std::fs::remove_file(input).ok();
+        std::fs::remove_file(output).ok();
+    }
+
+    #[test]
+    fn test_generate_pdf_matches_page_ranges_by_file() {
+        let png = create_test_png("test_generate_ranges.png");
+        let source = std::env::temp_dir().join("test_generate_ranges_source.pdf");
+        let output = std::env::temp_dir().join("test_generate_ranges.pdf");
+        let run = |inputs: Vec<PathBuf>, settings: PdfSettings, output: &Path| {
+            generate_pdf(
+                inputs
+                    .iter()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect(),
+                output.to_string_lossy().to_string(),
+                settings,
+                &|_| {},
+                &AtomicBool::new(false),
+            )
+        };
+        run(vec![png.clone(); 3], test_settings(), &source).unwrap();
+
+        // The input is spelled differently from the key
+        let ranges = |key: &Path| PdfSettings {
+            page_ranges: Some(HashMap::from([(
+                key.to_string_lossy().to_string(),
+                "2".to_string(),
+            )])),
+            ..test_settings()
+        };
+        let input = std::env::temp_dir()
+            .join(".")
+            .join("test_generate_ranges_source.pdf");
+        let stats = run(vec![input.clone()], ranges(&source), &output).unwrap();
+        assert_eq!(stats.page_count, 1);
+
+        let missing = std::env::temp_dir().join("test_generate_ranges_missing.pdf");
+        match run(vec![input], ranges(&missing), &output) {
+            Err(AppError::InvalidSettings(message)) => {
+                assert!(message.ends_with("test_generate_ranges_missing.pdf"))
+            }
+            other => panic!("expected invalid settings, got {:?}", other),
+        }
+
+        std::fs::remove_file(png).ok();
+        std::fs::remove_file(source).ok();
+        std::fs::remove_file(output).ok();
+    }
+
+    #[test]
+    fn test_generate_pdf_cancelled_leaves_no_output() {
+        let input = create_test_png("test_generate_cancelled.png");
+        let output = std::env::temp_dir().join("test_generate_cancelled.pdf");
+
+        let result = generate_pdf(
+            vec![input.to_string_lossy().to_string()],
+            output.to_string_lossy().to_string(),
+            test_settings(),
+            &|_| {},
+            &AtomicBool::new(true),
+        );
+
+        assert!(matches!(result, Err(AppError::Cancelled)));
+        assert!(!output.exists());
+        assert!(!PathBuf::from(format!("{}.part", output.display())).exists());
+
+        std::fs::remove_file(input).ok();
+    }
+}
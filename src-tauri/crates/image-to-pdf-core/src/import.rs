@@ -0,0 +1,323 @@
+use crate::embed::compress_flate;
+use crate::error::{AppError, Result};
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::{HashMap, HashSet};
+
+/// Levels of a page tree followed before giving up on a malformed one
+pub const MAX_TREE_DEPTH: usize = 32;
+
+/// Copies objects from another document into the one being built, giving
+/// them new IDs there
+///
+/// Objects reachable through several references are copied once per importer.
+pub struct ObjectImporter<'a> {
+    source: &'a Document,
+    ids: HashMap<ObjectId, ObjectId>,
+    /// Objects whose references become null instead of being copied
+    skipped: HashSet<ObjectId>,
+}
+
+impl<'a> ObjectImporter<'a> {
+    pub fn new(source: &'a Document) -> Self {
+        ObjectImporter {
+            source,
+            ids: HashMap::new(),
+            skipped: HashSet::new(),
+        }
+    }
+
+    /// Copy `object` and everything it references, returning it with its
+    /// references pointing at the copies
+    pub fn import(&mut self, doc: &mut Document, object: &Object) -> Result<Object> {
+        Ok(match object {
+            Object::Reference(id) if self.skipped.contains(id) => Object::Null,
+            Object::Reference(id) => Object::Reference(self.import_reference(doc, *id)?),
+            Object::Array(items) => Object::Array(
+                items
+                    .iter()
+                    .map(|item| self.import(doc, item))
+                    .collect::<Result<_>>()?,
+            ),
+            Object::Dictionary(dict) => Object::Dictionary(self.import_dictionary(doc, dict)?),
+            Object::Stream(stream) => Object::Stream(Stream {
+                dict: self.import_dictionary(doc, &stream.dict)?,
+                ..stream.clone()
+            }),
+            other => other.clone(),
+        })
+    }
+
+    fn import_dictionary(&mut self, doc: &mut Document, dict: &Dictionary) -> Result<Dictionary> {
+        let mut copy = Dictionary::new();
+        for (key, value) in dict.iter() {
+            copy.set(key.clone(), self.import(doc, value)?);
+        }
+        Ok(copy)
+    }
+
+    fn import_reference(&mut self, doc: &mut Document, id: ObjectId) -> Result<ObjectId> {
+        if let Some(&copied) = self.ids.get(&id) {
+            return Ok(copied);
+        }
+        // Reserve the ID first so reference cycles end here
+        let copied = doc.new_object_id();
+        self.ids.insert(id, copied);
+
+        // Dangling references are null, as readers treat them
+        let object = match self.source.get_object(id) {
+            Ok(object) => self.import(doc, object)?,
+            Err(_) => Object::Null,
+        };
+        doc.objects.insert(copied, object);
+        Ok(copied)
+    }
+}
+
+/// Page attributes that pages inherit from their ancestors in the page tree
+const INHERITED_ATTRIBUTES: [&[u8]; 4] = [b"Resources", b"MediaBox", b"CropBox", b"Rotate"];
+
+/// Copy pages of `source` into the document as children of `parent`,
+/// returning their new IDs in order
+///
+/// Inherited attributes are set on each copy. References to the rest of the
+/// page tree, such as links to pages that are not copied, become null rather
+/// than pulling the whole source document in.
+pub fn import_pages(
+    doc: &mut Document,
+    source: &Document,
+    pages: &[ObjectId],
+    parent: ObjectId,
+) -> Result<Vec<ObjectId>> {
+    let mut importer = ObjectImporter::new(source);
+    // Every page and page tree node, and the catalog that leads to them
+    for &page_id in source.get_pages().values() {
+        let mut node = Some(page_id);
+        for _ in 0..MAX_TREE_DEPTH {
+            let Some(id) = node.filter(|&id| importer.skipped.insert(id)) else {
+                break;
+            };
+            node = source
+                .get_dictionary(id)
+                .and_then(|dict| dict.get(b"Parent"))
+                .and_then(Object::as_reference)
+                .ok();
+        }
+    }
+    if let Ok(root) = source.trailer.get(b"Root").and_then(Object::as_reference) {
+        importer.skipped.insert(root);
+    }
+
+    // Links between copied pages keep pointing at the (first) copy
+    let mut page_ids = Vec::with_capacity(pages.len());
+    for &page_id in pages {
+        let copied = match importer.ids.get(&page_id) {
+            Some(_) => doc.new_object_id(),
+            None => {
+                let copied = doc.new_object_id();
+                importer.skipped.remove(&page_id);
+                importer.ids.insert(page_id, copied);
+                copied
+            }
+        };
+        page_ids.push(copied);
+    }
+
+    for (&page_id, &copied) in pages.iter().zip(&page_ids) {
+        let original = source
+            .get_dictionary(page_id)
+            .map_err(|e| AppError::ImageReadError(format!("Failed to read page: {}", e)))?;
+        let mut page = original.clone();
+        for key in INHERITED_ATTRIBUTES {
+            if !page.has(key) {
+                if let Some(value) = inherited_attribute(source, original, key) {
+                    page.set(key, value.clone());
+                }
+            }
+        }
+        page.remove(b"Parent");
+
+        let mut page = importer.import_dictionary(doc, &page)?;
+        page.set("Parent", parent);
+        doc.objects.insert(copied, Object::Dictionary(page));
+    }
+
+    Ok(page_ids)
+}
+
+/// Value of a page attribute, from the page itself or its nearest ancestor
+/// that sets it
+pub fn inherited_attribute<'a>(
+    source: &'a Document,
+    page: &'a Dictionary,
+    key: &[u8],
+) -> Option<&'a Object> {
+    let mut node = page;
+    for _ in 0..MAX_TREE_DEPTH {
+        if let Ok(value) = node.get(key) {
+            return Some(value);
+        }
+        node = node
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .and_then(|id| source.get_dictionary(id))
+            .ok()?;
+    }
+    None
+}
+
+/// Add the first page of `source` as a Form XObject drawn in the unit
+/// square, like an image XObject
+pub fn add_page_as_form(doc: &mut Document, source: &Document) -> Result<ObjectId> {
+    let read_error =
+        |e: lopdf::Error| AppError::ImageReadError(format!("Failed to read page: {}", e));
+
+    let page_id = *source
+        .get_pages()
+        .get(&1)
+        .ok_or_else(|| AppError::ImageReadError("Document has no pages".to_string()))?;
+    let page = source.get_dictionary(page_id).map_err(read_error)?;
+    let [x0, y0, x1, y1] = page_box(source, page)?;
+    let (width, height) = (x1 - x0, y1 - y0);
+    if width <= 0.0 || height <= 0.0 {
+        return Err(AppError::ImageReadError("Page is empty".to_string()));
+    }
+
+    let content = compress_flate(&source.get_page_content(page_id).map_err(read_error)?)?;
+    let resources = match inherited_attribute(source, page, b"Resources") {
+        Some(resources) => ObjectImporter::new(source).import(doc, resources)?,
+        None => Object::Dictionary(Dictionary::new()),
+    };
+
+    Ok(doc.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => vec![x0.into(), y0.into(), x1.into(), y1.into()],
+            // Map the page box onto the unit square images are drawn in
+            "Matrix" => vec![
+                (1.0 / width).into(),
+                0.into(),
+                0.into(),
+                (1.0 / height).into(),
+                (-x0 / width).into(),
+                (-y0 / height).into(),
+            ],
+            "Resources" => resources,
+            "Filter" => "FlateDecode",
+            "Length" => content.len() as i64,
+        },
+        content,
+    )))
+}
+
+/// Media box of a page, which may be inherited from the page tree
+pub fn page_box(source: &Document, page: &Dictionary) -> Result<[f32; 4]> {
+    let media_box = inherited_attribute(source, page, b"MediaBox")
+        .ok_or_else(|| AppError::ImageReadError("Page has no size".to_string()))?;
+    let values = source
+        .dereference(media_box)
+        .and_then(|(_, object)| object.as_array())
+        .map_err(|e| AppError::ImageReadError(format!("Invalid page size: {}", e)))?
+        .iter()
+        .map(|value| {
+            source
+                .dereference(value)
+                .and_then(|(_, value)| value.as_float())
+        })
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| AppError::ImageReadError(format!("Invalid page size: {}", e)))?;
+
+    match values[..] {
+        [x0, y0, x1, y1] => Ok([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]),
+        _ => Err(AppError::ImageReadError("Invalid page size".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_importer_copies_shared_objects_once() {
+        let mut source = Document::with_version("1.5");
+        let shared = source.add_object(dictionary! { "Type" => "ExtGState" });
+        let node = source.new_object_id();
+        // A node that refers back to itself
+        source.objects.insert(
+            node,
+            Object::Dictionary(dictionary! { "Self" => node, "State" => shared }),
+        );
+
+        let mut doc = Document::with_version("1.5");
+        doc.add_object(Object::Null);
+        let resources = Object::Array(vec![node.into(), shared.into(), node.into()]);
+        let copy = ObjectImporter::new(&source)
+            .import(&mut doc, &resources)
+            .unwrap();
+
+        // The node and the state, next to the object already in the document
+        assert_eq!(doc.objects.len(), 3);
+        let ids: Vec<_> = copy
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item.as_reference().unwrap())
+            .collect();
+        assert_eq!(ids[0], ids[2]);
+        let copied_node = doc.get_dictionary(ids[0]).unwrap();
+        assert_eq!(
+            copied_node.get(b"Self").unwrap().as_reference().unwrap(),
+            ids[0]
+        );
+        assert_eq!(
+            copied_node.get(b"State").unwrap().as_reference().unwrap(),
+            ids[1]
+        );
+    }
+
+    #[test]
+    fn test_import_pages_resolves_inherited_attributes() {
+        let mut source = Document::with_version("1.5");
+        let pages_id = source.new_object_id();
+        let second = source.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id });
+        let link = dictionary! {
+            "Subtype" => "Link",
+            "Dest" => vec![second.into(), "Fit".into()],
+        };
+        let first = source.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Annots" => vec![Object::Dictionary(link)],
+        });
+        source.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![first.into(), second.into()],
+                "Count" => 2,
+                "MediaBox" => vec![0.into(), 0.into(), 200.into(), 100.into()],
+                "Rotate" => 90,
+            }),
+        );
+        let catalog = source.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        source.trailer.set("Root", catalog);
+
+        let mut doc = Document::with_version("1.5");
+        let parent = doc.new_object_id();
+        let copied = import_pages(&mut doc, &source, &[first, first], parent).unwrap();
+
+        // Two copies of the page and nothing else from the page tree
+        assert_eq!(copied.len(), 2);
+        assert_ne!(copied[0], copied[1]);
+        assert_eq!(doc.objects.len(), 2);
+        let page = doc.get_dictionary(copied[1]).unwrap();
+        assert_eq!(page.get(b"Parent").unwrap().as_reference().unwrap(), parent);
+        assert_eq!(page.get(b"Rotate").unwrap().as_i64().unwrap(), 90);
+        assert_eq!(page_box(&doc, page).unwrap(), [0.0, 0.0, 200.0, 100.0]);
+
+        // The link to the page left behind points nowhere
+        let annots = page.get(b"Annots").unwrap().as_array().unwrap();
+        let dest = annots[0].as_dict().unwrap().get(b"Dest").unwrap();
+        assert_eq!(dest.as_array().unwrap()[0], Object::Null);
+    }
+}
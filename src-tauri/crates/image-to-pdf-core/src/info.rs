@@ -0,0 +1,163 @@
+use crate::error::{AppError, Result};
+use crate::utils::frames::frame_count;
+use crate::utils::heif::{decode_heif, heif_dimensions};
+use crate::utils::orientation::{load_oriented_image, oriented_dimensions};
+use crate::utils::pdf::{load_pdf, pdf_summary};
+use crate::utils::svg::{parse_svg, render_svg, svg_size};
+use crate::utils::validation::{validate_image, InputFormat};
+use image::metadata::Orientation;
+use image::{ImageDecoder, ImageReader};
+use serde::{Deserialize, Serialize};
+
+/// Image metadata
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageInfo {
+    /// Upright size in pixels; for a PDF, the first page in points
+    pub width: u32,
+    pub height: u32,
+    /// Format detected from the file content, e.g. `JPG`
+    pub format: String,
+    pub size_bytes: u64,
+    /// Pages of a PDF or TIFF file or frames of an animated GIF/WebP, otherwise 1
+    pub frame_count: u32,
+}
+
+/// Get metadata for a single image
+pub fn image_info(path: &str) -> Result<ImageInfo> {
+    // Validate first
+    let input_format = validate_image(path)?;
+
+    let (width, height, frame_count) = match input_format {
+        InputFormat::Image(format) => {
+            // Read the header only; width and height are reported upright
+            let mut reader = ImageReader::open(path)?;
+            reader.set_format(format);
+            let mut decoder = reader.into_decoder()?;
+            let (width, height) = decoder.dimensions();
+            // Corrupt EXIF is ignored, as when the image is converted
+            let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+            let (width, height) = oriented_dimensions(width, height, orientation);
+
+            let frame_count = frame_count(&std::fs::read(path)?, format)? as u32;
+            (width, height, frame_count)
+        }
+        // Only the primary image of a HEIF file is added
+        InputFormat::Heic | InputFormat::Avif => {
+            let (width, height) = heif_dimensions(&std::fs::read(path)?)?;
+            (width, height, 1)
+        }
+        // Size of the drawing in CSS pixels
+        InputFormat::Svg => {
+            let (width, height) = svg_size(&parse_svg(&std::fs::read(path)?)?);
+            (width.round() as u32, height.round() as u32, 1)
+        }
+        InputFormat::Pdf => {
+            let pdf = load_pdf(&std::fs::read(path)?)?;
+            let (page_count, (width, height)) = pdf_summary(&pdf)?;
+            (width.round() as u32, height.round() as u32, page_count as u32)
+        }
+    };
+
+    // Get file size
+    let metadata = std::fs::metadata(path)?;
+    let size_bytes = metadata.len();
+
+    Ok(ImageInfo {
+        width,
+        height,
+        format: input_format.name(),
+        size_bytes,
+        frame_count,
+    })
+}
+
+/// Render a PNG thumbnail that fits in a `size` x `size` box
+pub fn image_thumbnail(path: &str, size: u32) -> Result<Vec<u8>> {
+    // Validate first
+    let input_format = validate_image(path)?;
+
+    // Open image, turned upright
+    let data = std::fs::read(path)?;
+    let img = match input_format {
+        InputFormat::Image(format) => load_oriented_image(&data, format)
+            .map_err(|e| AppError::ImageReadError(format!("Failed to open image: {}", e)))?,
+        InputFormat::Heic | InputFormat::Avif => decode_heif(&data)?.image,
+        // Drawn at the thumbnail size rather than scaled down
+        InputFormat::Svg => render_svg(&parse_svg(&data)?, size)?,
+        InputFormat::Pdf => {
+            return Err(AppError::UnsupportedFormat(
+                "PDF (pages cannot be previewed)".to_string(),
+            ))
+        }
+    };
+
+    // Create thumbnail (maintaining aspect ratio)
+    let thumbnail = img.thumbnail(size, size);
+
+    // Convert to PNG bytes
+    let mut png_bytes: Vec<u8> = Vec::new();
+    thumbnail
+        .write_to(
+            &mut std::io::Cursor::new(&mut png_bytes),
+            image::ImageFormat::Png,
+        )
+        .map_err(|e| AppError::ImageProcessingError(format!("Failed to encode thumbnail: {}", e)))?;
+
+    Ok(png_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::orientation::tests::oriented_jpeg;
+
+    #[test]
+    fn test_image_info_reports_upright_dimensions() {
+        let path = std::env::temp_dir().join("test_image_info_rotated.jpg");
+        std::fs::write(&path, oriented_jpeg(40, 20, 8)).unwrap();
+        let path_str = path.to_string_lossy();
+
+        let info = image_info(&path_str).unwrap();
+        assert_eq!((info.width, info.height), (20, 40));
+        assert_eq!(info.format, "JPG");
+        assert_eq!(info.frame_count, 1);
+
+        let thumbnail = image::load_from_memory(&image_thumbnail(&path_str, 10).unwrap()).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (5, 10));
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_image_info_reports_frame_count() {
+        let path = std::env::temp_dir().join("test_image_info_frames.gif");
+        std::fs::write(&path, crate::utils::frames::tests::animated_gif(&[0, 50, 100, 150])).unwrap();
+
+        let info = image_info(&path.to_string_lossy()).unwrap();
+        assert_eq!(info.frame_count, 4);
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_image_info_reports_svg_size() {
+        let path = std::env::temp_dir().join("test_image_info_drawing.svg");
+        std::fs::write(
+            &path,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 40 20"><circle cx="10" cy="10" r="8"/></svg>"#,
+        )
+        .unwrap();
+        let path_str = path.to_string_lossy();
+
+        let info = image_info(&path_str).unwrap();
+        assert_eq!((info.width, info.height), (40, 20));
+        assert_eq!(info.format, "SVG");
+
+        // Rendered at the thumbnail size, even when larger than the drawing
+        let thumbnail = image::load_from_memory(&image_thumbnail(&path_str, 80).unwrap()).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (80, 40));
+
+        std::fs::remove_file(path).ok();
+    }
+}
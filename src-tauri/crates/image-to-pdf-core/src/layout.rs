@@ -0,0 +1,547 @@
+use crate::error::{AppError, Result};
+use crate::settings::{
+    Alignment, FillOrder, FitMode, GridLayout, Margins, Orientation, PageSize, PdfSettings,
+};
+use crate::utils::dpi::Dpi;
+use image::metadata::Orientation as ExifOrientation;
+
+// ============================================================================
+// Page Dimensions (in points: 1 point = 1/72 inch)
+// ============================================================================
+
+/// Get page dimensions in points based on page size and orientation
+///
+/// `image_size` is the physical size in points of the image placed on the
+/// page; only `PageSize::MatchImage` and `Orientation::Auto` depend on it.
+pub fn get_page_dimensions(settings: &PdfSettings, image_size: (f32, f32)) -> Result<(f32, f32)> {
+    // Standard sizes in points
+    let (mut width, mut height) = match settings.page_size {
+        PageSize::A4 => (595.0, 842.0),        // 210mm x 297mm
+        PageSize::A3 => (842.0, 1191.0),       // 297mm x 420mm
+        PageSize::A5 => (420.0, 595.0),        // 148mm x 210mm
+        PageSize::Letter => (612.0, 792.0),    // 8.5" x 11"
+        PageSize::Legal => (612.0, 1008.0),    // 8.5" x 14"
+        PageSize::Custom => {
+            let w = settings
+                .custom_width
+                .ok_or(AppError::InvalidDimensions)?;
+            let h = settings
+                .custom_height
+                .ok_or(AppError::InvalidDimensions)?;
+
+            if w <= 0.0 || h <= 0.0 {
+                return Err(AppError::InvalidDimensions);
+            }
+
+            // Convert mm to points (1 mm = 2.83465 points)
+            (w * 2.83465, h * 2.83465)
+        }
+        // The page already follows the image, so orientation does not apply
+        PageSize::MatchImage => return Ok(image_size),
+    };
+
+    // Swap dimensions for landscape, or when the page should follow a wide image
+    let swap = match settings.orientation {
+        Orientation::Portrait => false,
+        Orientation::Landscape => true,
+        Orientation::Auto => (image_size.0 > image_size.1) != (width > height),
+    };
+    if swap {
+        std::mem::swap(&mut width, &mut height);
+    }
+
+    Ok((width, height))
+}
+
+// ============================================================================
+// Image Placement Calculation
+// ============================================================================
+
+/// Position and size of an image on the page, in points
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePlacement {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Physical size of an image in points (1/72 inch) at the given resolution
+pub fn image_size_points(width: u32, height: u32, dpi: Dpi) -> (f32, f32) {
+    (width as f32 * 72.0 / dpi.x, height as f32 * 72.0 / dpi.y)
+}
+
+/// Printable area of a page inside the margins, in points
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentArea {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Compute the content area of a page, rejecting margins that leave no room
+pub fn content_area(page_width: f32, page_height: f32, margins: &Margins) -> Result<ContentArea> {
+    let [top, right, bottom, left] = margins.to_points();
+
+    if [top, right, bottom, left]
+        .iter()
+        .any(|m| m.is_nan() || *m < 0.0)
+    {
+        return Err(AppError::InvalidSettings(format!(
+            "Invalid margins: {:?}",
+            margins
+        )));
+    }
+
+    let area = ContentArea {
+        x: left,
+        y: bottom,
+        width: page_width - left - right,
+        height: page_height - top - bottom,
+    };
+
+    if area.width <= 0.0 || area.height <= 0.0 {
+        return Err(AppError::InvalidSettings(
+            "Margins leave no room for the image".to_string(),
+        ));
+    }
+
+    Ok(area)
+}
+
+/// Most cells a grid page may have, far beyond anything legible on paper
+const MAX_GRID_CELLS: u32 = 1024;
+
+/// Split the content area into grid cells, listed in the grid's fill order
+pub fn grid_cells(area: &ContentArea, grid: &GridLayout) -> Result<Vec<ContentArea>> {
+    let gutter = grid.gutter * grid.unit.to_points();
+
+    if grid.rows == 0 || grid.columns == 0 || gutter.is_nan() || gutter < 0.0 {
+        return Err(AppError::InvalidSettings(format!("Invalid grid layout: {:?}", grid)));
+    }
+    if !matches!(grid.rows.checked_mul(grid.columns), Some(count) if count <= MAX_GRID_CELLS) {
+        return Err(AppError::InvalidSettings(format!(
+            "Grid {}x{} has more than {} cells",
+            grid.rows, grid.columns, MAX_GRID_CELLS
+        )));
+    }
+
+    let (rows, columns) = (grid.rows as f32, grid.columns as f32);
+    let cell_width = (area.width - gutter * (columns - 1.0)) / columns;
+    let cell_height = (area.height - gutter * (rows - 1.0)) / rows;
+
+    if cell_width <= 0.0 || cell_height <= 0.0 {
+        return Err(AppError::InvalidSettings(
+            "Grid gutters leave no room for the images".to_string(),
+        ));
+    }
+
+    // Rows are counted from the top of the page
+    let cell = |row: u32, column: u32| ContentArea {
+        x: area.x + column as f32 * (cell_width + gutter),
+        y: area.y + area.height - (row + 1) as f32 * cell_height - row as f32 * gutter,
+        width: cell_width,
+        height: cell_height,
+    };
+
+    let cells = match grid.fill_order {
+        FillOrder::RowMajor => (0..grid.rows)
+            .flat_map(|row| (0..grid.columns).map(move |column| (row, column)))
+            .map(|(row, column)| cell(row, column))
+            .collect(),
+        FillOrder::ColumnMajor => (0..grid.columns)
+            .flat_map(|column| (0..grid.rows).map(move |row| (row, column)))
+            .map(|(row, column)| cell(row, column))
+            .collect(),
+    };
+
+    Ok(cells)
+}
+
+/// Calculate image position and dimensions based on fit mode and alignment
+///
+/// The image size is its physical size in points, see [`image_size_points`].
+pub fn calculate_image_placement(
+    img_width: f32,
+    img_height: f32,
+    area: &ContentArea,
+    fit_mode: &FitMode,
+    alignment: Alignment,
+) -> ImagePlacement {
+    let scale_w = area.width / img_width;
+    let scale_h = area.height / img_height;
+
+    let (width, height) = match fit_mode {
+        // Fit image to the area preserving aspect ratio
+        FitMode::Fit => scaled(img_width, img_height, scale_w.min(scale_h)),
+        // Fill the area, may crop image
+        FitMode::Fill => scaled(img_width, img_height, scale_w.max(scale_h)),
+        // Use physical size
+        FitMode::Original => (img_width, img_height),
+        // Fit, but keep small images at their physical size
+        FitMode::ShrinkOnly => scaled(img_width, img_height, scale_w.min(scale_h).min(1.0)),
+        FitMode::FitWidth => scaled(img_width, img_height, scale_w),
+        FitMode::FitHeight => scaled(img_width, img_height, scale_h),
+        FitMode::Stretch => (area.width, area.height),
+    };
+
+    // Fraction of the free space placed left of / below the image
+    let (align_x, align_y) = match alignment {
+        Alignment::TopLeft => (0.0, 1.0),
+        Alignment::Top => (0.5, 1.0),
+        Alignment::TopRight => (1.0, 1.0),
+        Alignment::Left => (0.0, 0.5),
+        Alignment::Center => (0.5, 0.5),
+        Alignment::Right => (1.0, 0.5),
+        Alignment::BottomLeft => (0.0, 0.0),
+        Alignment::Bottom => (0.5, 0.0),
+        Alignment::BottomRight => (1.0, 0.0),
+    };
+
+    ImagePlacement {
+        x: area.x + (area.width - width) * align_x,
+        y: area.y + (area.height - height) * align_y,
+        width,
+        height,
+    }
+}
+
+fn scaled(width: f32, height: f32, scale: f32) -> (f32, f32) {
+    (width * scale, height * scale)
+}
+
+/// Build the `cm` matrix that draws the unit-square image XObject into `placement`
+///
+/// The placement describes the upright image; the EXIF orientation is applied
+/// inside it, so JPEG data can be embedded untouched and still display upright.
+pub fn placement_matrix(placement: &ImagePlacement, orientation: ExifOrientation) -> [f32; 6] {
+    // Maps image space (u, v) onto the upright unit square
+    let [a, b, c, d, e, f] = match orientation {
+        ExifOrientation::NoTransforms => [1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+        ExifOrientation::FlipHorizontal => [-1.0, 0.0, 0.0, 1.0, 1.0, 0.0],
+        ExifOrientation::Rotate180 => [-1.0, 0.0, 0.0, -1.0, 1.0, 1.0],
+        ExifOrientation::FlipVertical => [1.0, 0.0, 0.0, -1.0, 0.0, 1.0],
+        ExifOrientation::Rotate90 => [0.0, -1.0, 1.0, 0.0, 0.0, 1.0],
+        ExifOrientation::Rotate270 => [0.0, 1.0, -1.0, 0.0, 1.0, 0.0],
+        ExifOrientation::Rotate90FlipH => [0.0, -1.0, -1.0, 0.0, 1.0, 1.0],
+        ExifOrientation::Rotate270FlipH => [0.0, 1.0, 1.0, 0.0, 0.0, 0.0],
+    };
+
+    let (w, h) = (placement.width, placement.height);
+    [
+        a * w,
+        b * h,
+        c * w,
+        d * h,
+        e * w + placement.x,
+        f * h + placement.y,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::LengthUnit;
+
+    #[test]
+    fn test_get_page_dimensions_a4_portrait() {
+        let settings = PdfSettings {
+            page_size: PageSize::A4,
+            custom_width: None,
+            custom_height: None,
+            orientation: Orientation::Portrait,
+            fit_mode: FitMode::Fit,
+            optimize_images: Some(true),
+            ..Default::default()
+        };
+
+        let (w, h) = get_page_dimensions(&settings, (100.0, 100.0)).unwrap();
+        assert_eq!(w, 595.0);
+        assert_eq!(h, 842.0);
+    }
+
+    #[test]
+    fn test_get_page_dimensions_a4_landscape() {
+        let settings = PdfSettings {
+            page_size: PageSize::A4,
+            custom_width: None,
+            custom_height: None,
+            orientation: Orientation::Landscape,
+            fit_mode: FitMode::Fit,
+            optimize_images: Some(true),
+            ..Default::default()
+        };
+
+        let (w, h) = get_page_dimensions(&settings, (100.0, 100.0)).unwrap();
+        assert_eq!(w, 842.0);
+        assert_eq!(h, 595.0);
+    }
+
+    #[test]
+    fn test_get_page_dimensions_custom() {
+        let settings = PdfSettings {
+            page_size: PageSize::Custom,
+            custom_width: Some(100.0),
+            custom_height: Some(200.0),
+            orientation: Orientation::Portrait,
+            fit_mode: FitMode::Fit,
+            optimize_images: Some(true),
+            ..Default::default()
+        };
+
+        let (w, h) = get_page_dimensions(&settings, (100.0, 100.0)).unwrap();
+        assert!((w - 283.465).abs() < 0.01);
+        assert!((h - 566.93).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_get_page_dimensions_auto_follows_image() {
+        let settings = PdfSettings {
+            orientation: Orientation::Auto,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            get_page_dimensions(&settings, (400.0, 300.0)).unwrap(),
+            (842.0, 595.0)
+        );
+        assert_eq!(
+            get_page_dimensions(&settings, (300.0, 400.0)).unwrap(),
+            (595.0, 842.0)
+        );
+        // Square images keep the page portrait
+        assert_eq!(
+            get_page_dimensions(&settings, (300.0, 300.0)).unwrap(),
+            (595.0, 842.0)
+        );
+    }
+
+    #[test]
+    fn test_get_page_dimensions_match_image() {
+        let settings = PdfSettings {
+            page_size: PageSize::MatchImage,
+            orientation: Orientation::Landscape,
+            ..Default::default()
+        };
+
+        let (w, h) = get_page_dimensions(&settings, (144.0, 288.0)).unwrap();
+        assert_eq!((w, h), (144.0, 288.0));
+    }
+
+    #[test]
+    fn test_original_uses_physical_size() {
+        // A 300 DPI scan of 2480x3508 pixels is an A4 page
+        let (w, h) = image_size_points(2480, 3508, Dpi { x: 300.0, y: 300.0 });
+        let placement =
+            calculate_image_placement(w, h, &a4_area(), &FitMode::Original, Alignment::Center);
+
+        assert!((placement.width - 595.2).abs() < 0.01);
+        assert!((placement.height - 841.92).abs() < 0.01);
+        assert!(placement.x.abs() < 0.2 && placement.y.abs() < 0.2);
+    }
+
+    fn a4_area() -> ContentArea {
+        content_area(595.0, 842.0, &Margins::default()).unwrap()
+    }
+
+    #[test]
+    fn test_content_area_margins() {
+        let margins = Margins {
+            top: 10.0,
+            right: 20.0,
+            bottom: 30.0,
+            left: 40.0,
+            unit: LengthUnit::Pt,
+        };
+
+        let area = content_area(595.0, 842.0, &margins).unwrap();
+        assert_eq!(
+            area,
+            ContentArea {
+                x: 40.0,
+                y: 30.0,
+                width: 535.0,
+                height: 802.0
+            }
+        );
+
+        let mm = Margins {
+            top: 10.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+            unit: LengthUnit::Mm,
+        };
+        let area = content_area(595.0, 842.0, &mm).unwrap();
+        assert!((area.x - 28.3465).abs() < 0.001);
+        assert!((area.width - 538.307).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_content_area_rejects_invalid_margins() {
+        let negative = Margins {
+            top: -1.0,
+            ..Default::default()
+        };
+        assert!(matches!(
+            content_area(595.0, 842.0, &negative),
+            Err(AppError::InvalidSettings(_))
+        ));
+
+        let too_wide = Margins {
+            left: 300.0,
+            right: 300.0,
+            unit: LengthUnit::Pt,
+            ..Default::default()
+        };
+        assert!(matches!(
+            content_area(595.0, 842.0, &too_wide),
+            Err(AppError::InvalidSettings(_))
+        ));
+    }
+
+    #[test]
+    fn test_shrink_only_keeps_small_images() {
+        let small = calculate_image_placement(
+            100.0,
+            50.0,
+            &a4_area(),
+            &FitMode::ShrinkOnly,
+            Alignment::Center,
+        );
+        assert_eq!(
+            small,
+            ImagePlacement {
+                x: 247.5,
+                y: 396.0,
+                width: 100.0,
+                height: 50.0
+            }
+        );
+
+        let large = calculate_image_placement(
+            1190.0,
+            842.0,
+            &a4_area(),
+            &FitMode::ShrinkOnly,
+            Alignment::Center,
+        );
+        assert_eq!((large.width, large.height), (595.0, 421.0));
+    }
+
+    #[test]
+    fn test_fit_width_height_and_stretch() {
+        let area = a4_area();
+
+        let width =
+            calculate_image_placement(100.0, 100.0, &area, &FitMode::FitWidth, Alignment::Center);
+        assert_eq!((width.width, width.height), (595.0, 595.0));
+
+        let height =
+            calculate_image_placement(100.0, 100.0, &area, &FitMode::FitHeight, Alignment::Center);
+        assert_eq!((height.width, height.height), (842.0, 842.0));
+        assert_eq!(height.x, -123.5);
+
+        let stretch =
+            calculate_image_placement(100.0, 100.0, &area, &FitMode::Stretch, Alignment::Center);
+        assert_eq!(
+            stretch,
+            ImagePlacement {
+                x: 0.0,
+                y: 0.0,
+                width: 595.0,
+                height: 842.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_alignment_anchors() {
+        let margins = Margins {
+            top: 10.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+            unit: LengthUnit::Pt,
+        };
+        let area = content_area(595.0, 842.0, &margins).unwrap();
+        let place = |alignment| {
+            let p = calculate_image_placement(100.0, 50.0, &area, &FitMode::Original, alignment);
+            (p.x, p.y)
+        };
+
+        assert_eq!(place(Alignment::TopLeft), (10.0, 782.0));
+        assert_eq!(place(Alignment::Top), (247.5, 782.0));
+        assert_eq!(place(Alignment::Right), (485.0, 396.0));
+        assert_eq!(place(Alignment::BottomLeft), (10.0, 10.0));
+        assert_eq!(place(Alignment::BottomRight), (485.0, 10.0));
+    }
+
+    #[test]
+    fn test_grid_cells_fill_order() {
+        let area = ContentArea { x: 0.0, y: 0.0, width: 210.0, height: 100.0 };
+        let mut grid = GridLayout {
+            rows: 2,
+            columns: 2,
+            gutter: 10.0,
+            unit: LengthUnit::Pt,
+            fill_order: FillOrder::RowMajor,
+        };
+
+        let cells = grid_cells(&area, &grid).unwrap();
+        let origins: Vec<_> = cells.iter().map(|c| (c.x, c.y)).collect();
+        assert_eq!(origins, vec![(0.0, 55.0), (110.0, 55.0), (0.0, 0.0), (110.0, 0.0)]);
+        assert_eq!((cells[0].width, cells[0].height), (100.0, 45.0));
+
+        grid.fill_order = FillOrder::ColumnMajor;
+        let cells = grid_cells(&area, &grid).unwrap();
+        let origins: Vec<_> = cells.iter().map(|c| (c.x, c.y)).collect();
+        assert_eq!(origins, vec![(0.0, 55.0), (0.0, 0.0), (110.0, 55.0), (110.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_grid_cells_rejects_empty_or_oversized_grid() {
+        let area = ContentArea { x: 0.0, y: 0.0, width: 100.0, height: 100.0 };
+        let grid = GridLayout {
+            rows: 0,
+            columns: 2,
+            gutter: 0.0,
+            unit: LengthUnit::Pt,
+            fill_order: FillOrder::RowMajor,
+        };
+        assert!(matches!(grid_cells(&area, &grid), Err(AppError::InvalidSettings(_))));
+
+        let grid = GridLayout { rows: 1, gutter: 200.0, ..grid };
+        assert!(matches!(grid_cells(&area, &grid), Err(AppError::InvalidSettings(_))));
+
+        // Rejected before any cell is allocated, even when the count overflows
+        for (rows, columns) in [(100, 100), (100_000, 100_000)] {
+            let grid = GridLayout { rows, columns, gutter: 0.0, ..grid.clone() };
+            assert!(matches!(grid_cells(&area, &grid), Err(AppError::InvalidSettings(_))));
+        }
+    }
+
+    #[test]
+    fn test_placement_matrix_applies_orientation() {
+        let placement = ImagePlacement {
+            x: 10.0,
+            y: 20.0,
+            width: 100.0,
+            height: 50.0,
+        };
+
+        assert_eq!(
+            placement_matrix(&placement, ExifOrientation::NoTransforms),
+            [100.0, 0.0, 0.0, 50.0, 10.0, 20.0]
+        );
+
+        // Image's top-left corner (u=0, v=1) lands on the upright top-right corner
+        let m = placement_matrix(&placement, ExifOrientation::Rotate90);
+        let (u, v) = (0.0, 1.0);
+        assert_eq!(
+            (m[0] * u + m[2] * v + m[4], m[1] * u + m[3] * v + m[5]),
+            (110.0, 70.0)
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! Image to PDF conversion engine
+//!
+//! This crate contains everything needed to turn images into a PDF and has no
+//! dependency on Tauri, so it can be used from the desktop app, the
+//! command-line binary and other Rust services alike.
+//!
+//! Use [`PdfBuilder`] to assemble a document page by page, or
+//! [`generate_pdf`] to convert a list of files with progress reporting and
+//! cancellation. [`PdfBuilder::stream_to`] and [`generate_pdf`] write pages
+//! as they are built, so memory use does not grow with the number of images.
+
+// Modules
+mod append;
+mod builder;
+mod embed;
+mod encryption;
+mod error;
+mod generate;
+mod import;
+mod info;
+mod layout;
+mod metadata;
+mod pdfa;
+mod placeholder;
+mod prepare;
+mod settings;
+pub mod utils;
+mod writer;
+
+pub use builder::PdfBuilder;
+pub use error::{AppError, Result};
+pub use generate::{
+    generate_pdf, GenerationProgress, GenerationStats, ImageFailure, ImageStats, ProgressStage,
+};
+pub use info::{image_info, image_thumbnail, ImageInfo};
+pub use layout::{
+    calculate_image_placement, content_area, get_page_dimensions, grid_cells, ContentArea,
+    ImagePlacement,
+};
+pub use prepare::ImageEncoding;
+pub use settings::{
+    Alignment, AppendTo, Compliance, DocumentMetadata, Encryption, EncryptionAlgorithm, ErrorMode,
+    FillOrder, FitMode, FrameSelection, GridLayout, LengthUnit, Margins, Orientation, PageSize,
+    PdfSettings, Permissions, TransparencyMode,
+};
@@ -0,0 +1,232 @@
+use crate::error::{AppError, Result};
+use crate::settings::{Compliance, DocumentMetadata};
+use chrono::{DateTime, FixedOffset, Utc};
+use lopdf::{text_string, Dictionary, Object};
+
+/// Producer written when the settings do not name one
+pub const DEFAULT_PRODUCER: &str = concat!("image-to-pdf-core ", env!("CARGO_PKG_VERSION"));
+
+/// Document metadata resolved once from `PdfSettings`
+#[derive(Debug, Clone)]
+pub struct DocumentInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    pub creator: Option<String>,
+    pub producer: String,
+    pub created: DateTime<FixedOffset>,
+    pub modified: DateTime<FixedOffset>,
+}
+
+impl DocumentInfo {
+    /// Resolve metadata, defaulting the creation date to now and the
+    /// modification date to the creation date
+    pub fn from_settings(metadata: Option<&DocumentMetadata>) -> Result<Self> {
+        let metadata = metadata.cloned().unwrap_or_default();
+        let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+
+        let created = match &metadata.creation_date {
+            Some(date) => parse_date(date)?,
+            None => Utc::now().fixed_offset(),
+        };
+        let modified = match &metadata.modification_date {
+            Some(date) => parse_date(date)?,
+            None => created,
+        };
+
+        Ok(DocumentInfo {
+            title: non_empty(metadata.title),
+            author: non_empty(metadata.author),
+            subject: non_empty(metadata.subject),
+            keywords: non_empty(metadata.keywords),
+            creator: non_empty(metadata.creator),
+            producer: non_empty(metadata.producer).unwrap_or_else(|| DEFAULT_PRODUCER.to_string()),
+            created,
+            modified,
+        })
+    }
+
+    /// Document information dictionary (`/Info` in the trailer)
+    pub fn info_dictionary(&self) -> Dictionary {
+        let mut info = Dictionary::new();
+        let text_fields = [
+            ("Title", &self.title),
+            ("Author", &self.author),
+            ("Subject", &self.subject),
+            ("Keywords", &self.keywords),
+            ("Creator", &self.creator),
+        ];
+
+        for (key, value) in text_fields {
+            if let Some(value) = value {
+                info.set(key, text_string(value));
+            }
+        }
+        info.set("Producer", text_string(&self.producer));
+        info.set(
+            "CreationDate",
+            Object::string_literal(pdf_date(&self.created)),
+        );
+        info.set("ModDate", Object::string_literal(pdf_date(&self.modified)));
+        info
+    }
+
+    /// XMP packet carrying the same values as the Info dictionary, plus the
+    /// PDF/A identification when the output claims conformance
+    pub fn xmp_packet(&self, compliance: Option<Compliance>) -> String {
+        let mut properties = String::from("   <dc:format>application/pdf</dc:format>\n");
+        let mut namespaces = "";
+
+        if let Some(Compliance::PdfA2b) = compliance {
+            namespaces = "\n    xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\"";
+            properties += "   <pdfaid:part>2</pdfaid:part>\n";
+            properties += "   <pdfaid:conformance>B</pdfaid:conformance>\n";
+        }
+
+        if let Some(title) = &self.title {
+            properties += &format!(
+                "   <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n",
+                escape_xml(title)
+            );
+        }
+        if let Some(author) = &self.author {
+            properties += &format!(
+                "   <dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+                escape_xml(author)
+            );
+        }
+        if let Some(subject) = &self.subject {
+            properties += &format!(
+                "   <dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>\n",
+                escape_xml(subject)
+            );
+        }
+        if let Some(keywords) = &self.keywords {
+            properties += &format!("   <pdf:Keywords>{}</pdf:Keywords>\n", escape_xml(keywords));
+        }
+        if let Some(creator) = &self.creator {
+            properties += &format!(
+                "   <xmp:CreatorTool>{}</xmp:CreatorTool>\n",
+                escape_xml(creator)
+            );
+        }
+        properties += &format!(
+            "   <pdf:Producer>{}</pdf:Producer>\n",
+            escape_xml(&self.producer)
+        );
+        properties += &format!(
+            "   <xmp:CreateDate>{}</xmp:CreateDate>\n",
+            self.created.to_rfc3339()
+        );
+        properties += &format!(
+            "   <xmp:ModifyDate>{}</xmp:ModifyDate>\n",
+            self.modified.to_rfc3339()
+        );
+        properties += &format!(
+            "   <xmp:MetadataDate>{}</xmp:MetadataDate>\n",
+            self.modified.to_rfc3339()
+        );
+
+        format!(
+            "<?xpacket begin=\"\u{FEFF}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+             <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+             <rdf:Description rdf:about=\"\"\n    \
+             xmlns:dc=\"http://purl.org/dc/elements/1.1/\"\n    \
+             xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\"\n    \
+             xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\"{}>\n\
+             {}  \
+             </rdf:Description>\n \
+             </rdf:RDF>\n\
+             </x:xmpmeta>\n\
+             <?xpacket end=\"w\"?>",
+            namespaces, properties
+        )
+    }
+}
+
+/// Parse an RFC 3339 date such as `2024-05-01T12:00:00+02:00`
+pub fn parse_date(value: &str) -> Result<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map_err(|e| AppError::InvalidSettings(format!("Invalid date {}: {}", value, e)))
+}
+
+/// Format a date as a PDF date string, e.g. `D:20240501120000+02'00'`
+pub fn pdf_date(date: &DateTime<FixedOffset>) -> String {
+    let offset_minutes = date.offset().local_minus_utc() / 60;
+    let zone = if offset_minutes == 0 {
+        "Z".to_string()
+    } else {
+        let sign = if offset_minutes < 0 { '-' } else { '+' };
+        let minutes = offset_minutes.abs();
+        format!("{}{:02}'{:02}'", sign, minutes / 60, minutes % 60)
+    };
+
+    format!("D:{}{}", date.format("%Y%m%d%H%M%S"), zone)
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pdf_date() {
+        let date = parse_date("2024-05-01T12:30:05+02:00").unwrap();
+        assert_eq!(pdf_date(&date), "D:20240501123005+02'00'");
+
+        let date = parse_date("2024-05-01T12:30:05Z").unwrap();
+        assert_eq!(pdf_date(&date), "D:20240501123005Z");
+
+        assert!(matches!(
+            parse_date("yesterday"),
+            Err(AppError::InvalidSettings(_))
+        ));
+    }
+
+    #[test]
+    fn test_info_and_xmp_match() {
+        let metadata = DocumentMetadata {
+            title: Some("Отчёт <Q1> & more".to_string()),
+            author: Some("Jane Doe".to_string()),
+            keywords: Some("scan, receipts".to_string()),
+            creation_date: Some("2024-05-01T12:00:00Z".to_string()),
+            ..Default::default()
+        };
+        let info = DocumentInfo::from_settings(Some(&metadata)).unwrap();
+
+        let dict = info.info_dictionary();
+        let title = lopdf::decode_text_string(dict.get(b"Title").unwrap()).unwrap();
+        assert_eq!(title, "Отчёт <Q1> & more");
+        assert_eq!(
+            dict.get(b"ModDate").unwrap().as_str().unwrap(),
+            b"D:20240501120000Z"
+        );
+        assert!(dict.get(b"Subject").is_err());
+
+        let xmp = info.xmp_packet(None);
+        assert!(xmp.contains("<rdf:li xml:lang=\"x-default\">Отчёт &lt;Q1&gt; &amp; more</rdf:li>"));
+        assert!(
+            xmp.contains("<dc:creator><rdf:Seq><rdf:li>Jane Doe</rdf:li></rdf:Seq></dc:creator>")
+        );
+        assert!(xmp.contains("<xmp:CreateDate>2024-05-01T12:00:00+00:00</xmp:CreateDate>"));
+        assert!(xmp.contains(&format!(
+            "<pdf:Producer>{}</pdf:Producer>",
+            DEFAULT_PRODUCER
+        )));
+        assert!(!xmp.contains("pdfaid"));
+
+        let xmp = info.xmp_packet(Some(Compliance::PdfA2b));
+        assert!(xmp.contains("xmlns:pdfaid=\"http://www.aiim.org/pdfa/ns/id/\">"));
+        assert!(xmp.contains("<pdfaid:part>2</pdfaid:part>"));
+        assert!(xmp.contains("<pdfaid:conformance>B</pdfaid:conformance>"));
+    }
+}
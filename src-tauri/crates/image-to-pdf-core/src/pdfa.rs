@@ -0,0 +1,147 @@
+use crate::error::{AppError, Result};
+use crate::utils::jpeg::JpegInfo;
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+
+/// Highest PDF version allowed by PDF/A-2
+pub const PDFA_VERSION: &str = "1.7";
+
+/// Page size limits of PDF/A-2 (ISO 32000-1 Annex C), in points
+const MIN_PAGE_SIZE: f32 = 3.0;
+const MAX_PAGE_SIZE: f32 = 14400.0;
+
+const OUTPUT_CONDITION: &str = "sRGB IEC61966-2.1";
+
+/// Compact ICC v4 sRGB profile for the output intent
+pub fn srgb_icc_profile() -> Result<Vec<u8>> {
+    let mut profile = moxcms::ColorProfile::new_srgb();
+    // The CICP tag would make this a v4.3 profile, newer than PDF 1.7 knows
+    profile.cicp = None;
+    profile
+        .encode()
+        .map_err(|e| AppError::PdfGenerationError(format!("Cannot build sRGB profile: {:?}", e)))
+}
+
+/// Add the sRGB PDF/A output intent, returning its object ID for the
+/// Catalog's `/OutputIntents`
+pub fn add_output_intent(doc: &mut Document) -> Result<ObjectId> {
+    let profile = srgb_icc_profile()?;
+    let profile_id = doc.add_object(Stream::new(
+        dictionary! {
+            "N" => 3,
+            "Length" => profile.len() as i64,
+        },
+        profile,
+    ));
+
+    Ok(doc.add_object(dictionary! {
+        "Type" => "OutputIntent",
+        "S" => "GTS_PDFA1",
+        "OutputConditionIdentifier" => Object::string_literal(OUTPUT_CONDITION),
+        "Info" => Object::string_literal(OUTPUT_CONDITION),
+        "RegistryName" => Object::string_literal("http://www.color.org"),
+        "DestOutputProfile" => profile_id,
+    }))
+}
+
+/// Reject JPEGs whose colours cannot be expressed against an sRGB output
+/// intent: CMYK without an embedded profile, or a profile readers may not
+/// understand
+pub fn check_jpeg(info: &JpegInfo) -> Result<()> {
+    match &info.icc_profile {
+        Some(profile) => check_icc_profile(profile, info.components),
+        None if info.components == 4 => Err(AppError::NotCompliant(
+            "CMYK JPEG without an embedded ICC profile".to_string(),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Validate the header of an embedded ICC profile
+pub fn check_icc_profile(profile: &[u8], components: u8) -> Result<()> {
+    if profile.len() < 128 || &profile[36..40] != b"acsp" {
+        return Err(AppError::NotCompliant(
+            "embedded ICC profile is damaged".to_string(),
+        ));
+    }
+    if profile[8] > 4 {
+        return Err(AppError::NotCompliant(format!(
+            "embedded ICC profile version {}.{} is newer than PDF/A-2 allows",
+            profile[8],
+            profile[9] >> 4
+        )));
+    }
+
+    let expected: &[u8; 4] = match components {
+        1 => b"GRAY",
+        3 => b"RGB ",
+        _ => b"CMYK",
+    };
+    if &profile[16..20] != expected {
+        return Err(AppError::NotCompliant(
+            "embedded ICC profile does not match the image colour space".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Reject pages outside the PDF/A-2 size limits
+pub fn check_page_size(width: f32, height: f32) -> Result<()> {
+    let in_range = |side: f32| (MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&side);
+    if in_range(width) && in_range(height) {
+        return Ok(());
+    }
+
+    Err(AppError::NotCompliant(format!(
+        "page size {:.0}x{:.0} pt is outside {}-{} pt",
+        width, height, MIN_PAGE_SIZE, MAX_PAGE_SIZE
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jpeg_info(components: u8, icc_profile: Option<Vec<u8>>) -> JpegInfo {
+        JpegInfo {
+            width: 1,
+            height: 1,
+            components,
+            bits_per_component: 8,
+            adobe_transform: None,
+            icc_profile,
+            dpi: None,
+        }
+    }
+
+    #[test]
+    fn test_srgb_profile_header() {
+        let profile = srgb_icc_profile().unwrap();
+        assert!(check_icc_profile(&profile, 3).is_ok());
+        assert_eq!(profile[8], 4);
+        assert_eq!(
+            u32::from_be_bytes(profile[0..4].try_into().unwrap()) as usize,
+            profile.len()
+        );
+    }
+
+    #[test]
+    fn test_check_jpeg() {
+        assert!(check_jpeg(&jpeg_info(3, None)).is_ok());
+        assert!(matches!(
+            check_jpeg(&jpeg_info(4, None)),
+            Err(AppError::NotCompliant(_))
+        ));
+
+        let profile = srgb_icc_profile().unwrap();
+        assert!(check_jpeg(&jpeg_info(3, Some(profile.clone()))).is_ok());
+        assert!(check_jpeg(&jpeg_info(4, Some(profile))).is_err());
+        assert!(check_jpeg(&jpeg_info(3, Some(vec![0; 16]))).is_err());
+    }
+
+    #[test]
+    fn test_check_page_size() {
+        assert!(check_page_size(595.0, 842.0).is_ok());
+        assert!(check_page_size(20000.0, 842.0).is_err());
+        assert!(check_page_size(2.0, 2.0).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::layout::ContentArea;
+use lopdf::content::Operation;
+use lopdf::{dictionary, Dictionary, Object, StringFormat};
+
+/// Size of a placeholder on pages that match the image (A4 portrait), in points
+pub const PLACEHOLDER_SIZE: (f32, f32) = (595.0, 842.0);
+
+/// Average Helvetica glyph width relative to the font size, for wrapping
+const AVERAGE_CHAR_WIDTH: f32 = 0.55;
+
+/// Non-embedded standard font used for placeholder text
+pub fn font_dictionary() -> Dictionary {
+    dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    }
+}
+
+/// Draw a frame into `cell` with the file name and the reason it is missing
+///
+/// `font` is the resource name of [`font_dictionary`] on the page.
+pub fn placeholder_operations(
+    cell: &ContentArea,
+    font: &str,
+    name: &str,
+    reason: &str,
+) -> Vec<Operation> {
+    let font_size = (cell.width / 40.0).clamp(5.0, 12.0);
+    let leading = font_size * 1.4;
+    let padding = font_size * 2.0;
+    let columns =
+        (((cell.width - 2.0 * padding) / (font_size * AVERAGE_CHAR_WIDTH)) as usize).max(8);
+    let rows = ((cell.height - 2.0 * padding) / leading).max(1.0) as usize;
+
+    let mut lines = vec!["Image could not be added".to_string(), String::new()];
+    lines.extend(wrap(name, columns));
+    lines.push(String::new());
+    lines.extend(wrap(reason, columns));
+    lines.truncate(rows);
+
+    let mut operations = vec![
+        Operation::new("q", vec![]),
+        // Light grey frame just inside the cell
+        Operation::new("G", vec![0.6.into()]),
+        Operation::new(
+            "re",
+            vec![
+                (cell.x + font_size).into(),
+                (cell.y + font_size).into(),
+                (cell.width - 2.0 * font_size).into(),
+                (cell.height - 2.0 * font_size).into(),
+            ],
+        ),
+        Operation::new("S", vec![]),
+        Operation::new("BT", vec![]),
+        Operation::new(
+            "Tf",
+            vec![Object::Name(font.as_bytes().to_vec()), font_size.into()],
+        ),
+        Operation::new("TL", vec![leading.into()]),
+        Operation::new(
+            "Td",
+            vec![
+                (cell.x + padding).into(),
+                (cell.y + cell.height - padding - font_size).into(),
+            ],
+        ),
+    ];
+    for line in lines {
+        operations.push(Operation::new(
+            "Tj",
+            vec![Object::String(win_ansi(&line), StringFormat::Literal)],
+        ));
+        operations.push(Operation::new("T*", vec![]));
+    }
+    operations.extend([Operation::new("ET", vec![]), Operation::new("Q", vec![])]);
+    operations
+}
+
+/// Break text into lines of at most `columns` characters, at spaces where possible
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        // Words longer than a line, such as paths, are split anywhere
+        while word.len() > columns {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            lines.push(word.drain(..columns).collect());
+        }
+
+        let word: String = word.into_iter().collect();
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > columns {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Encode text for `WinAnsiEncoding`; characters it lacks become `?`
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{A0}'..='\u{FF}' => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("Failed to read image: bad header", 12),
+            vec!["Failed to", "read image:", "bad header"]
+        );
+        assert_eq!(
+            wrap("/very/long/path.png", 8),
+            vec!["/very/lo", "ng/path.", "png"]
+        );
+        assert!(wrap("   ", 8).is_empty());
+    }
+
+    #[test]
+    fn test_win_ansi() {
+        assert_eq!(win_ansi("café Фото.jpg"), b"caf\xe9 ????.jpg".to_vec());
+    }
+}
@@ -0,0 +1,496 @@
+use crate::embed::{
+    add_encoded_raster, add_jpeg_image, encode_raster, EncodedRaster, ImageOptions,
+};
+use crate::error::{AppError, Result};
+use crate::import::{add_page_as_form, import_pages};
+use crate::layout::image_size_points;
+use crate::pdfa::{check_icc_profile, check_jpeg};
+use crate::settings::{FrameSelection, PdfSettings};
+use crate::utils::dpi::{read_dpi, Dpi, DEFAULT_DPI};
+use crate::utils::frames::{
+    for_each_frame, frame_count, select_frames, tiff_page, tiff_page_offsets,
+};
+use crate::utils::heif::decode_heif;
+use crate::utils::jpeg::{parse_jpeg_header, JpegInfo};
+use crate::utils::optimize::optimize_image_data;
+use crate::utils::orientation::{read_orientation, swaps_dimensions};
+use crate::utils::pdf::{load_pdf, pdf_summary, PageRanges};
+use crate::utils::svg::{parse_svg, svg_size, svg_to_pdf, SVG_DPI};
+use crate::utils::validation::{sniff_format, validate_image, InputFormat};
+use image::metadata::Orientation as ExifOrientation;
+use image::ImageFormat;
+use lopdf::{Document, ObjectId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Per-image work that needs no access to the document: reading,
+/// optimization, decoding and compression
+///
+/// It only holds settings, so one preparer can be shared by worker threads
+/// while the builder embeds their results in page order.
+pub struct ImagePreparer {
+    options: ImageOptions,
+    fallback_dpi: Dpi,
+    frames: FrameSelection,
+    /// Pages to copy from PDF inputs, by canonical input path
+    page_ranges: HashMap<PathBuf, PageRanges>,
+    /// Reject JPEGs, SVG and PDF inputs that PDF/A output cannot describe
+    check_compliance: bool,
+}
+
+/// An input file read into memory, whose images are prepared one at a time
+pub struct InputFile {
+    data: Vec<u8>,
+    format: InputFormat,
+    /// Pages to copy from a PDF input
+    pages: Option<PageRanges>,
+    /// Frames to add from a multi-page or animated image; `None` adds the
+    /// file as a single image
+    frames: Option<Range<usize>>,
+}
+
+impl InputFile {
+    /// Number of images the file adds, one per selected frame
+    pub fn image_count(&self) -> usize {
+        self.frames.as_ref().map_or(1, |frames| frames.len())
+    }
+
+    /// Zero-based index of the first frame the file adds
+    pub fn first_frame(&self) -> usize {
+        self.frames.as_ref().map_or(0, |frames| frames.start)
+    }
+}
+
+/// An image ready to be embedded as an XObject and placed on a page
+pub struct PreparedImage {
+    /// Content hash, so identical images are embedded once
+    pub key: [u8; 32],
+    pub width: u32,
+    pub height: u32,
+    /// Transform still to be applied to the embedded pixels
+    pub orientation: ExifOrientation,
+    /// Resolution of the embedded pixels
+    pub dpi: Dpi,
+    pub encoding: ImageEncoding,
+    /// Bytes the PNG/JPEG optimization step removed from the input data
+    pub optimization_saved: u64,
+    data: ImageData,
+}
+
+/// How an image is stored in the PDF
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum ImageEncoding {
+    /// Original JPEG data with `DCTDecode`
+    DctPassthrough,
+    /// JPEG re-encoded smaller by optimization, with `DCTDecode`
+    DctReencoded,
+    /// Decoded pixels compressed with `FlateDecode`
+    Flate,
+    /// Vector drawing in a Form XObject
+    Vector,
+    /// Pages copied from a PDF input
+    PdfPages,
+}
+
+enum ImageData {
+    /// JPEG passed through with `DCTDecode`
+    Jpeg { info: JpegInfo, data: Vec<u8> },
+    /// Upright pixels, already Flate-compressed
+    Raster(EncodedRaster),
+    /// One-page PDF converted from an SVG drawing, whose page becomes a Form
+    /// XObject of `width` x `height` points
+    Vector {
+        pdf: Box<Document>,
+        pdf_len: u64,
+        width: f32,
+        height: f32,
+    },
+    /// PDF input and the pages of it to copy, in order
+    Pages {
+        pdf: Box<Document>,
+        pdf_len: u64,
+        pages: Vec<ObjectId>,
+    },
+}
+
+impl PreparedImage {
+    /// Size of the image data streams, including any soft mask and profile
+    pub fn encoded_len(&self) -> u64 {
+        match &self.data {
+            ImageData::Jpeg { data, .. } => data.len() as u64,
+            ImageData::Raster(raster) => {
+                let mask = raster.mask.as_ref().map_or(0, Vec::len);
+                let profile = raster.icc_profile.as_ref().map_or(0, Vec::len);
+                (raster.color.len() + mask + profile) as u64
+            }
+            ImageData::Vector { pdf_len, .. } | ImageData::Pages { pdf_len, .. } => *pdf_len,
+        }
+    }
+
+    /// Physical size of the embedded image before `orientation` is applied,
+    /// in points
+    pub fn size_points(&self) -> (f32, f32) {
+        match &self.data {
+            ImageData::Vector { width, height, .. } => (*width, *height),
+            _ => image_size_points(self.width, self.height, self.dpi),
+        }
+    }
+
+    /// Whether this holds pages of a PDF input, which are copied with
+    /// [`PreparedImage::import_pages`] instead of being embedded and placed
+    pub fn is_pdf_pages(&self) -> bool {
+        matches!(self.data, ImageData::Pages { .. })
+    }
+
+    /// Add the image XObject to the document
+    pub fn embed(self, doc: &mut Document) -> Result<ObjectId> {
+        Ok(match self.data {
+            ImageData::Jpeg { info, data } => add_jpeg_image(doc, &info, data),
+            ImageData::Raster(raster) => add_encoded_raster(doc, raster),
+            ImageData::Vector { pdf, .. } => add_page_as_form(doc, &pdf)?,
+            ImageData::Pages { .. } => {
+                return Err(AppError::PdfGenerationError(
+                    "PDF pages cannot be placed as an image".to_string(),
+                ))
+            }
+        })
+    }
+
+    /// Copy the selected pages of a PDF input into the document as children
+    /// of `parent`, returning their IDs
+    pub fn import_pages(self, doc: &mut Document, parent: ObjectId) -> Result<Vec<ObjectId>> {
+        match self.data {
+            ImageData::Pages { pdf, pages, .. } => import_pages(doc, &pdf, &pages, parent),
+            _ => Err(AppError::PdfGenerationError(
+                "Only PDF inputs have pages to copy".to_string(),
+            )),
+        }
+    }
+}
+
+/// Absolute form of an input path, so `a.pdf`, `./a.pdf` and `/dir/a.pdf`
+/// name the same file; paths that do not exist are kept as given
+pub(crate) fn canonical_path(path: impl AsRef<Path>) -> PathBuf {
+    std::fs::canonicalize(&path).unwrap_or_else(|_| path.as_ref().to_path_buf())
+}
+
+impl ImagePreparer {
+    /// Resolve image options once, validating the fallback DPI and frame range
+    pub fn from_settings(settings: &PdfSettings) -> Result<Self> {
+        let fallback_dpi = settings.fallback_dpi.unwrap_or(DEFAULT_DPI);
+        if fallback_dpi.is_nan() || fallback_dpi <= 0.0 {
+            return Err(AppError::InvalidSettings(format!(
+                "Invalid fallback DPI: {}",
+                fallback_dpi
+            )));
+        }
+
+        let frames = settings.frames.clone().unwrap_or_default();
+        if let FrameSelection::Range { start, end } = frames {
+            if start == 0 || end < start {
+                return Err(AppError::InvalidSettings(format!(
+                    "Invalid frame range: {}-{}",
+                    start, end
+                )));
+            }
+        }
+
+        let page_ranges = settings
+            .page_ranges
+            .iter()
+            .flatten()
+            .map(|(path, spec)| Ok((canonical_path(path), PageRanges::parse(spec)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(ImagePreparer {
+            options: ImageOptions::from_settings(settings)?,
+            fallback_dpi: Dpi {
+                x: fallback_dpi,
+                y: fallback_dpi,
+            },
+            frames,
+            page_ranges,
+            check_compliance: settings.compliance.is_some(),
+        })
+    }
+
+    /// Read an image file, selecting the frames to add from it
+    ///
+    /// A PDF file is added as one entry holding the pages its range in
+    /// `page_ranges` selects.
+    pub fn read_file(&self, path: &Path) -> Result<InputFile> {
+        let path = path.to_string_lossy();
+        let format = validate_image(&path)?;
+        let data = std::fs::read(&*path)?;
+
+        self.read_data(data, format, self.page_ranges.get(&canonical_path(&*path)).cloned())
+    }
+
+    /// Read an in-memory image, detecting its format from the content
+    ///
+    /// All pages of a PDF are added.
+    pub fn read_bytes(&self, data: &[u8]) -> Result<InputFile> {
+        let format = sniff_format(data)?;
+
+        self.read_data(data.to_vec(), format, None)
+    }
+
+    fn read_data(
+        &self,
+        data: Vec<u8>,
+        format: InputFormat,
+        pages: Option<PageRanges>,
+    ) -> Result<InputFile> {
+        // Expand multi-page and animated files into one image per selected
+        // frame, counting frames without decoding them
+        let frames = match format {
+            InputFormat::Image(format @ (ImageFormat::Tiff | ImageFormat::Gif | ImageFormat::WebP))
+                if self.frames != FrameSelection::First =>
+            {
+                let count = frame_count(&data, format)?;
+                if count > 1 {
+                    Some(select_frames(&self.frames, count)?)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        Ok(InputFile {
+            data,
+            format,
+            pages,
+            frames,
+        })
+    }
+
+    /// Prepare the images of an input file in order, handing each to `add`
+    /// before the next frame is decoded
+    pub fn prepare_each(
+        &self,
+        input: InputFile,
+        mut add: impl FnMut(PreparedImage) -> Result<()>,
+    ) -> Result<()> {
+        let InputFile {
+            data,
+            format,
+            pages,
+            frames,
+        } = input;
+        let format = match format {
+            InputFormat::Image(format) => format,
+            // Only the primary image of a HEIF file is added
+            InputFormat::Heic | InputFormat::Avif => return add(self.prepare_heif(&data)?),
+            InputFormat::Svg => return add(self.prepare_svg(&data)?),
+            InputFormat::Pdf => return add(self.prepare_pdf(&data, pages.as_ref())?),
+        };
+
+        let Some(frames) = frames else {
+            return add(self.prepare_single(data, format)?);
+        };
+        if format == ImageFormat::Tiff {
+            for &offset in &tiff_page_offsets(&data)?[frames] {
+                add(self.prepare_single(tiff_page(&data, offset)?, format)?)?;
+            }
+            return Ok(());
+        }
+        for_each_frame(&data, format, frames, |frame| {
+            add(self.prepare_decoded(&frame, self.fallback_dpi, None)?)
+        })?;
+        Ok(())
+    }
+
+    fn prepare_single(&self, data: Vec<u8>, format: ImageFormat) -> Result<PreparedImage> {
+        // Read orientation and resolution before optimization, which does not keep them
+        let orientation = read_orientation(&data, format);
+        let dpi = read_dpi(&data, format).unwrap_or(self.fallback_dpi);
+
+        // Optimize image before adding to PDF (if enabled), keeping the
+        // original when the optimized data is not smaller
+        let optimized = if self.options.optimize {
+            optimize_image_data(&data, format, 85)?.filter(|optimized| optimized.len() < data.len())
+        } else {
+            None
+        };
+        let reencoded = optimized.is_some();
+        let optimization_saved = optimized
+            .as_ref()
+            .map_or(0, |optimized| (data.len() - optimized.len()) as u64);
+        let data = optimized.unwrap_or(data);
+
+        match format {
+            ImageFormat::Jpeg => {
+                // Use original JPEG data with DCTDecode filter, rotating on the page
+                let info = parse_jpeg_header(&data)?;
+                if self.check_compliance {
+                    check_jpeg(&info)?;
+                }
+
+                Ok(PreparedImage {
+                    key: content_hash(&[b"jpeg", &data]),
+                    width: info.width,
+                    height: info.height,
+                    orientation,
+                    dpi,
+                    encoding: if reencoded {
+                        ImageEncoding::DctReencoded
+                    } else {
+                        ImageEncoding::DctPassthrough
+                    },
+                    optimization_saved,
+                    data: ImageData::Jpeg { info, data },
+                })
+            }
+            _ => {
+                let mut img = image::load_from_memory_with_format(&data, format)?;
+                img.apply_orientation(orientation);
+
+                // Resolution follows the pixels when they are turned a quarter
+                let dpi = if swaps_dimensions(orientation) {
+                    Dpi { x: dpi.y, y: dpi.x }
+                } else {
+                    dpi
+                };
+
+                let mut image = self.prepare_decoded(&img, dpi, None)?;
+                image.optimization_saved = optimization_saved;
+                Ok(image)
+            }
+        }
+    }
+
+    /// Decode a HEIF or AVIF image, keeping its colour profile
+    fn prepare_heif(&self, data: &[u8]) -> Result<PreparedImage> {
+        let heif = decode_heif(data)?;
+        // Drop profiles that do not describe RGB samples, which readers would reject
+        let icc_profile = heif
+            .icc_profile
+            .filter(|profile| check_icc_profile(profile, 3).is_ok());
+
+        self.prepare_decoded(&heif.image, self.fallback_dpi, icc_profile)
+    }
+
+    /// Convert an SVG drawing to PDF vector content
+    ///
+    /// The drawing keeps the size its `width` and `height` give it, with the
+    /// viewBox mapped into that box, so fit modes place it like an image.
+    fn prepare_svg(&self, data: &[u8]) -> Result<PreparedImage> {
+        // The converted drawing may use transparency groups, soft masks and
+        // uncalibrated colour, which are not checked
+        if self.check_compliance {
+            return Err(AppError::NotCompliant(
+                "SVG inputs may contain content PDF/A does not allow".to_string(),
+            ));
+        }
+
+        let tree = parse_svg(data)?;
+        let pdf_data = svg_to_pdf(&tree);
+        let pdf = Document::load_mem(&pdf_data)
+            .map_err(|e| AppError::ImageProcessingError(format!("Failed to convert SVG: {}", e)))?;
+
+        let (width, height) = svg_size(&tree);
+        let dpi = Dpi {
+            x: SVG_DPI,
+            y: SVG_DPI,
+        };
+        let (width_points, height_points) = (width * 72.0 / dpi.x, height * 72.0 / dpi.y);
+
+        Ok(PreparedImage {
+            key: content_hash(&[b"svg", data]),
+            width: width.round() as u32,
+            height: height.round() as u32,
+            orientation: ExifOrientation::NoTransforms,
+            dpi,
+            encoding: ImageEncoding::Vector,
+            optimization_saved: 0,
+            data: ImageData::Vector {
+                pdf: Box::new(pdf),
+                pdf_len: pdf_data.len() as u64,
+                width: width_points,
+                height: height_points,
+            },
+        })
+    }
+
+    /// Parse a PDF input and resolve which of its pages to copy
+    fn prepare_pdf(&self, data: &[u8], ranges: Option<&PageRanges>) -> Result<PreparedImage> {
+        // Fonts, transparency and colour of arbitrary PDFs are not checked
+        if self.check_compliance {
+            return Err(AppError::NotCompliant(
+                "PDF inputs may contain content PDF/A does not allow".to_string(),
+            ));
+        }
+
+        let pdf = load_pdf(data)?;
+        let all_pages: Vec<ObjectId> = pdf.get_pages().into_values().collect();
+        let pages = match ranges {
+            Some(ranges) => ranges
+                .select(all_pages.len())?
+                .into_iter()
+                .map(|index| all_pages[index])
+                .collect(),
+            None => all_pages,
+        };
+        let (_, (width, height)) = pdf_summary(&pdf)?;
+
+        Ok(PreparedImage {
+            key: content_hash(&[b"pdf", data]),
+            width: width.round() as u32,
+            height: height.round() as u32,
+            orientation: ExifOrientation::NoTransforms,
+            dpi: Dpi { x: 72.0, y: 72.0 },
+            encoding: ImageEncoding::PdfPages,
+            optimization_saved: 0,
+            data: ImageData::Pages {
+                pdf: Box::new(pdf),
+                pdf_len: data.len() as u64,
+                pages,
+            },
+        })
+    }
+
+    /// Compress upright pixels for an image XObject, tagged with an RGB
+    /// ICC profile if one is given
+    fn prepare_decoded(
+        &self,
+        img: &image::DynamicImage,
+        dpi: Dpi,
+        icc_profile: Option<Vec<u8>>,
+    ) -> Result<PreparedImage> {
+        let key = content_hash(&[
+            b"raster",
+            &img.width().to_le_bytes(),
+            &img.height().to_le_bytes(),
+            format!("{:?}", img.color()).as_bytes(),
+            img.as_bytes(),
+            icc_profile.as_deref().unwrap_or_default(),
+        ]);
+        let mut raster = encode_raster(img, self.options.transparency, self.options.background)?;
+        raster.icc_profile = icc_profile;
+
+        Ok(PreparedImage {
+            key,
+            width: img.width(),
+            height: img.height(),
+            orientation: ExifOrientation::NoTransforms,
+            dpi,
+            encoding: ImageEncoding::Flate,
+            optimization_saved: 0,
+            data: ImageData::Raster(raster),
+        })
+    }
+}
+
+/// SHA-256 over length-prefixed parts, so adjacent parts cannot run together
+fn content_hash(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
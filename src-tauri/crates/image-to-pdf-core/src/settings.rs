@@ -0,0 +1,287 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// ============================================================================
+// Types (must match TypeScript types exactly)
+// ============================================================================
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub enum PageSize {
+    #[default]
+    A4,
+    A3,
+    A5,
+    Letter,
+    Legal,
+    Custom,
+    /// Each page takes the physical size of its image
+    MatchImage,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub enum Orientation {
+    #[default]
+    Portrait,
+    Landscape,
+    /// Portrait or landscape per page, following the image's aspect ratio
+    Auto,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub enum FitMode {
+    #[default]
+    Fit,
+    Fill,
+    /// True physical size, from the image's DPI
+    Original,
+    /// Like `Fit`, but never upscale images smaller than the page
+    ShrinkOnly,
+    /// Scale to the content width; may overflow vertically
+    FitWidth,
+    /// Scale to the content height; may overflow horizontally
+    FitHeight,
+    /// Fill the content area exactly, ignoring the aspect ratio
+    Stretch,
+}
+
+/// Anchor point used to position an image inside the content area
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum Alignment {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// Which frames of multi-page TIFF and animated GIF/WebP files become pages
+///
+/// Images with a single frame are always added.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub enum FrameSelection {
+    #[default]
+    All,
+    First,
+    /// Frames `start..=end`, counted from 1
+    Range { start: u32, end: u32 },
+}
+
+/// Order in which consecutive images fill the cells of a grid
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum FillOrder {
+    /// Left to right, then top to bottom
+    #[default]
+    RowMajor,
+    /// Top to bottom, then left to right
+    ColumnMajor,
+}
+
+/// Several images per page, packed into a grid of equal cells
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GridLayout {
+    pub rows: u32,
+    pub columns: u32,
+    /// Space between cells, in `unit`
+    #[serde(default)]
+    pub gutter: f32,
+    #[serde(default)]
+    pub unit: LengthUnit,
+    #[serde(default)]
+    pub fill_order: FillOrder,
+}
+
+/// Unit for lengths given in settings
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum LengthUnit {
+    /// Millimetres
+    #[default]
+    Mm,
+    /// PDF points (1/72 inch)
+    Pt,
+}
+
+/// Page margins around the content area
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Margins {
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+    pub left: f32,
+    #[serde(default)]
+    pub unit: LengthUnit,
+}
+
+impl LengthUnit {
+    /// Points per unit
+    pub fn to_points(self) -> f32 {
+        match self {
+            LengthUnit::Mm => 2.83465,
+            LengthUnit::Pt => 1.0,
+        }
+    }
+}
+
+impl Margins {
+    /// Margins converted to points, as `[top, right, bottom, left]`
+    pub fn to_points(&self) -> [f32; 4] {
+        let scale = self.unit.to_points();
+        [self.top, self.right, self.bottom, self.left].map(|value| value * scale)
+    }
+}
+
+/// How to handle images that carry an alpha channel
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub enum TransparencyMode {
+    /// Keep transparency using a soft mask (`/SMask`)
+    #[default]
+    Preserve,
+    /// Composite the image onto `background_color`
+    Flatten,
+}
+
+/// Values for the Info dictionary and XMP metadata of the document
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub keywords: Option<String>,
+    /// Application that created the original content
+    pub creator: Option<String>,
+    /// Defaults to this library and its version
+    pub producer: Option<String>,
+    /// RFC 3339, e.g. `2024-05-01T12:00:00+02:00`; defaults to the time of generation
+    pub creation_date: Option<String>,
+    /// RFC 3339; defaults to the creation date
+    pub modification_date: Option<String>,
+}
+
+/// Standard security handler cipher
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum EncryptionAlgorithm {
+    /// AES-128 (PDF 1.6), for older readers
+    Aes128,
+    /// AES-256 (PDF 2.0)
+    #[default]
+    Aes256,
+}
+
+/// What readers may do without the owner password
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Permissions {
+    pub print: bool,
+    pub copy: bool,
+    pub modify: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Permissions {
+            print: true,
+            copy: true,
+            modify: true,
+        }
+    }
+}
+
+/// Password protection for the generated PDF
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Encryption {
+    /// Password needed to open the document; empty opens without one
+    #[serde(default)]
+    pub user_password: String,
+    /// Password that lifts the permission restrictions.
+    /// Defaults to a random one, so the restrictions cannot be lifted
+    pub owner_password: Option<String>,
+    #[serde(default)]
+    pub algorithm: EncryptionAlgorithm,
+    #[serde(default)]
+    pub permissions: Permissions,
+}
+
+/// Archival standard the output must conform to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Compliance {
+    /// PDF/A-2b (ISO 19005-2, basic conformance): sRGB output intent, XMP
+    /// identification and a file ID; no encryption
+    PdfA2b,
+}
+
+/// What a generation job does when an image cannot be read, decoded or embedded
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum ErrorMode {
+    /// Stop the job with the error
+    #[default]
+    Abort,
+    /// Leave the image out and carry on
+    Skip,
+    /// Put a page stating the file name and reason where the image would go
+    Placeholder,
+}
+
+/// Existing PDF that new pages are added to with an incremental update
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppendTo {
+    pub path: String,
+    /// Zero-based page the new pages are inserted before (defaults to after
+    /// the last page)
+    #[serde(default)]
+    pub index: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct PdfSettings {
+    pub page_size: PageSize,
+    pub custom_width: Option<f32>,
+    pub custom_height: Option<f32>,
+    pub orientation: Orientation,
+    pub fit_mode: FitMode,
+    pub optimize_images: Option<bool>,
+    /// Defaults to `Preserve`
+    pub transparency: Option<TransparencyMode>,
+    /// Background for `Flatten` as `#RRGGBB` (defaults to white)
+    pub background_color: Option<String>,
+    /// Resolution assumed for images that do not declare one (defaults to 72)
+    pub fallback_dpi: Option<f32>,
+    /// Defaults to no margins
+    pub margins: Option<Margins>,
+    /// Defaults to `Center`
+    pub alignment: Option<Alignment>,
+    /// Defaults to one image per page
+    pub grid: Option<GridLayout>,
+    /// Defaults to all frames
+    pub frames: Option<FrameSelection>,
+    /// Title, author and other document properties
+    pub metadata: Option<DocumentMetadata>,
+    /// Passwords and permissions (default: not encrypted)
+    pub encryption: Option<Encryption>,
+    /// Archival conformance (default: plain PDF)
+    pub compliance: Option<Compliance>,
+    /// Pack non-stream objects into compressed object streams with a
+    /// cross-reference stream (PDF 1.5). Defaults to false; encrypted output
+    /// always uses a classic cross-reference table, and an update appended to
+    /// a file uses the same kind of cross-reference section as the file
+    pub object_streams: Option<bool>,
+    /// Threads preparing images in parallel (defaults to one per CPU core)
+    pub workers: Option<usize>,
+    /// Defaults to `Abort`
+    pub on_error: Option<ErrorMode>,
+    /// Pages to copy from PDF inputs, keyed by input path, as ranges like
+    /// `1-3, 5, 8-` counted from 1. PDFs without an entry add all pages
+    pub page_ranges: Option<HashMap<String, String>>,
+    /// Add the pages to an existing PDF instead of starting a new one (default:
+    /// new document). The file's content, properties and signatures are kept
+    /// byte for byte, so `metadata` is not used; excludes encryption and PDF/A
+    pub append_to: Option<AppendTo>,
+}
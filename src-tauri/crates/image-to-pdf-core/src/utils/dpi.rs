@@ -0,0 +1,161 @@
+use crate::utils::jpeg::parse_jpeg_header;
+use image::ImageFormat;
+
+/// Default resolution when an image does not declare one (1 pixel = 1 point)
+pub const DEFAULT_DPI: f32 = 72.0;
+
+/// Horizontal and vertical resolution in pixels per inch
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dpi {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Dpi {
+    /// Build a resolution from a density in `unit`s (1 = inch, 2 = centimetre)
+    ///
+    /// Zero densities and unknown units (including "aspect ratio only") yield `None`.
+    pub fn from_density(x: f32, y: f32, unit: u8) -> Option<Dpi> {
+        let per_inch = match unit {
+            1 => 1.0,
+            2 => 2.54,
+            _ => return None,
+        };
+
+        (x > 0.0 && y > 0.0).then_some(Dpi {
+            x: x * per_inch,
+            y: y * per_inch,
+        })
+    }
+}
+
+/// Read the declared resolution of an in-memory image
+///
+/// Supports JPEG (JFIF and EXIF), PNG (`pHYs`) and TIFF resolution tags.
+pub fn read_dpi(data: &[u8], format: ImageFormat) -> Option<Dpi> {
+    match format {
+        ImageFormat::Jpeg => parse_jpeg_header(data).ok().and_then(|info| info.dpi),
+        ImageFormat::Png => png_dpi(data),
+        ImageFormat::Tiff => tiff_dpi(data),
+        _ => None,
+    }
+}
+
+/// Read the `pHYs` chunk of a PNG file, stopping at the first `IDAT`
+fn png_dpi(data: &[u8]) -> Option<Dpi> {
+    let mut pos = 8;
+
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let body = data.get(pos + 8..pos + 8 + length)?;
+
+        match kind {
+            // Unit 1 is pixels per metre; unit 0 only gives the aspect ratio
+            b"pHYs" if body.len() >= 9 && body[8] == 1 => {
+                let x = u32::from_be_bytes(body[0..4].try_into().ok()?) as f32;
+                let y = u32::from_be_bytes(body[4..8].try_into().ok()?) as f32;
+                return Dpi::from_density(x / 100.0, y / 100.0, 2);
+            }
+            b"pHYs" | b"IDAT" => return None,
+            _ => {}
+        }
+
+        pos += 12 + length;
+    }
+
+    None
+}
+
+/// Read XResolution, YResolution and ResolutionUnit from the first IFD of a
+/// TIFF structure (a TIFF file or the payload of an EXIF segment)
+pub fn tiff_dpi(data: &[u8]) -> Option<Dpi> {
+    let big_endian = match data.get(0..4)? {
+        [0x49, 0x49, 42, 0] => false,
+        [0x4D, 0x4D, 0, 42] => true,
+        _ => return None,
+    };
+    let u16_at = |pos: usize| -> Option<u16> {
+        let bytes = data.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    };
+    let u32_at = |pos: usize| -> Option<u32> {
+        let bytes = data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    };
+    let rational_at = |pos: usize| -> Option<f32> {
+        let offset = u32_at(pos)? as usize;
+        let (numerator, denominator) = (u32_at(offset)?, u32_at(offset + 4)?);
+        (denominator != 0).then(|| numerator as f32 / denominator as f32)
+    };
+
+    let ifd = u32_at(4)? as usize;
+    let (mut x, mut y, mut unit) = (None, None, 2);
+
+    for i in 0..u16_at(ifd)? as usize {
+        let entry = ifd + 2 + i * 12;
+        match u16_at(entry)? {
+            0x011A => x = rational_at(entry + 8),
+            0x011B => y = rational_at(entry + 8),
+            0x0128 => unit = u16_at(entry + 8)?,
+            _ => {}
+        }
+    }
+
+    // TIFF units: 2 = inch, 3 = centimetre
+    let unit = match unit {
+        2 => 1,
+        3 => 2,
+        _ => return None,
+    };
+    Dpi::from_density(x?, y?, unit)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Little-endian TIFF header with one IFD holding X/YResolution and ResolutionUnit
+    pub(crate) fn tiff_resolution(dpi: u32, unit: u16) -> Vec<u8> {
+        let mut data = b"II\x2a\0\x08\0\0\0\x03\0".to_vec();
+        data.extend_from_slice(&[0x1A, 0x01, 5, 0, 1, 0, 0, 0, 50, 0, 0, 0]);
+        data.extend_from_slice(&[0x1B, 0x01, 5, 0, 1, 0, 0, 0, 58, 0, 0, 0]);
+        data.extend_from_slice(&[0x28, 0x01, 3, 0, 1, 0, 0, 0]);
+        data.extend_from_slice(&unit.to_le_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        for _ in 0..2 {
+            data.extend_from_slice(&dpi.to_le_bytes());
+            data.extend_from_slice(&1u32.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_tiff_resolution_tags() {
+        assert_eq!(tiff_dpi(&tiff_resolution(300, 2)), Some(Dpi { x: 300.0, y: 300.0 }));
+        assert_eq!(tiff_dpi(&tiff_resolution(100, 3)), Some(Dpi { x: 254.0, y: 254.0 }));
+        assert_eq!(tiff_dpi(&tiff_resolution(300, 1)), None);
+    }
+
+    #[test]
+    fn test_png_phys_chunk() {
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgb8(4, 4)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        assert_eq!(read_dpi(&png, ImageFormat::Png), None);
+
+        // 11811 pixels per metre is 300 DPI; insert pHYs right after IHDR
+        let mut chunk = 9u32.to_be_bytes().to_vec();
+        chunk.extend_from_slice(b"pHYs");
+        chunk.extend_from_slice(&11811u32.to_be_bytes());
+        chunk.extend_from_slice(&11811u32.to_be_bytes());
+        chunk.extend_from_slice(&[1, 0, 0, 0, 0]);
+        let ihdr_end = 8 + 12 + 13;
+        png.splice(ihdr_end..ihdr_end, chunk);
+
+        let dpi = read_dpi(&png, ImageFormat::Png).unwrap();
+        assert!((dpi.x - 300.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::error::{AppError, Result};
+use crate::settings::FrameSelection;
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use image::{AnimationDecoder, DynamicImage, ImageFormat};
+use std::io::Cursor;
+use std::ops::Range;
+
+/// Indices of the frames to keep out of `count`
+///
+/// Fails when a range selects no frame of the image.
+pub fn select_frames(selection: &FrameSelection, count: usize) -> Result<Range<usize>> {
+    let range = match selection {
+        FrameSelection::All => 0..count,
+        FrameSelection::First => 0..count.min(1),
+        FrameSelection::Range { start, end } => {
+            (*start as usize).saturating_sub(1)..(*end as usize).min(count)
+        }
+    };
+
+    if range.is_empty() {
+        return Err(AppError::InvalidSettings(format!(
+            "Frame selection {:?} is outside the image's {} frames",
+            selection, count
+        )));
+    }
+
+    Ok(range)
+}
+
+/// Number of frames or pages in an in-memory image
+///
+/// Counts the pages of a TIFF file and the frames of an animated GIF or WebP
+/// by walking the file structure, without decoding any pixels; every other
+/// image has a single frame.
+pub fn frame_count(data: &[u8], format: ImageFormat) -> Result<usize> {
+    match format {
+        ImageFormat::Tiff => Ok(tiff_page_offsets(data)?.len()),
+        ImageFormat::Gif => gif_frame_count(data),
+        ImageFormat::WebP => Ok(webp_frame_count(data)?.max(1)),
+        _ => Ok(1),
+    }
+}
+
+/// Count the image descriptors of a GIF file, skipping colour tables,
+/// extensions and compressed image data
+fn gif_frame_count(data: &[u8]) -> Result<usize> {
+    let invalid = || AppError::ImageReadError("Invalid GIF structure".to_string());
+    // Colour table of 3 * 2^(n + 1) bytes, present if the flag bit is set
+    let color_table_len = |flags: u8| if flags & 0x80 != 0 { 3 << ((flags & 0x07) + 1) } else { 0 };
+    // Data sub-blocks, each prefixed by its length, up to an empty one
+    let skip_sub_blocks = |mut pos: usize| -> Result<usize> {
+        loop {
+            let len = *data.get(pos).ok_or_else(invalid)? as usize;
+            pos += 1 + len;
+            if len == 0 {
+                return Ok(pos);
+            }
+        }
+    };
+
+    // Header and logical screen descriptor
+    let flags = *data.get(10).ok_or_else(invalid)?;
+    let mut pos = 13 + color_table_len(flags);
+    let mut count = 0;
+    loop {
+        match data.get(pos) {
+            // Extension: label, then sub-blocks
+            Some(0x21) => pos = skip_sub_blocks(pos + 2)?,
+            // Image descriptor, optional local colour table, LZW code size, data
+            Some(0x2C) => {
+                let flags = *data.get(pos + 9).ok_or_else(invalid)?;
+                pos = skip_sub_blocks(pos + 10 + color_table_len(flags) + 1)?;
+                count += 1;
+            }
+            // Trailer, or a file cut short after its last complete frame
+            Some(0x3B) | None => return Ok(count),
+            Some(_) => return Err(invalid()),
+        }
+    }
+}
+
+/// Count the `ANMF` chunks of an animated WebP file; 0 for a still image
+fn webp_frame_count(data: &[u8]) -> Result<usize> {
+    if data.len() < 12 || &data[..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return Err(AppError::ImageReadError("Invalid WebP header".to_string()));
+    }
+
+    let mut count = 0;
+    let mut pos = 12;
+    while let Some(header) = data.get(pos..pos + 8) {
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        if &header[..4] == b"ANMF" {
+            count += 1;
+        }
+        // Chunks are padded to an even length
+        pos += 8 + len + (len & 1);
+    }
+
+    Ok(count)
+}
+
+/// Decode the frames in `range` of an animated GIF or WebP one at a time
+///
+/// Frames are composited onto the full canvas, as a viewer would show them.
+/// Returns the number of frames passed to `f`.
+pub fn for_each_frame(
+    data: &[u8],
+    format: ImageFormat,
+    range: Range<usize>,
+    mut f: impl FnMut(DynamicImage) -> Result<()>,
+) -> Result<usize> {
+    let frames = match format {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(data))?.into_frames(),
+        ImageFormat::WebP => WebPDecoder::new(Cursor::new(data))?.into_frames(),
+        _ => {
+            return Err(AppError::UnsupportedFormat(format!(
+                "{:?} has no animation frames",
+                format
+            )))
+        }
+    };
+
+    let mut count = 0;
+    for frame in frames.skip(range.start).take(range.len()) {
+        f(DynamicImage::ImageRgba8(frame?.into_buffer()))?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Offsets of every image file directory (page) in a TIFF file
+pub fn tiff_page_offsets(data: &[u8]) -> Result<Vec<u64>> {
+    let tiff_error = |e: tiff::TiffError| AppError::ImageReadError(format!("Invalid TIFF: {}", e));
+    let mut decoder = tiff::decoder::Decoder::new(Cursor::new(data)).map_err(tiff_error)?;
+
+    let mut offsets = Vec::new();
+    loop {
+        offsets.extend(decoder.ifd_pointer().map(|pointer| pointer.0));
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image().map_err(tiff_error)?;
+    }
+
+    Ok(offsets)
+}
+
+/// Copy of a TIFF file whose header points at the page at `offset`
+///
+/// Decoders read the first directory only, so this makes any page decodable
+/// (with its own resolution and orientation) without converting pixels by hand.
+pub fn tiff_page(data: &[u8], offset: u64) -> Result<Vec<u8>> {
+    let mut page = data.to_vec();
+    let big_endian = data.starts_with(b"MM");
+
+    match data.get(2..4) {
+        // Classic TIFF: 32-bit offset at byte 4
+        Some([0, 42]) | Some([42, 0]) => {
+            let offset = u32::try_from(offset)
+                .map_err(|_| AppError::ImageReadError("Invalid TIFF page offset".to_string()))?;
+            let bytes = if big_endian { offset.to_be_bytes() } else { offset.to_le_bytes() };
+            page[4..8].copy_from_slice(&bytes);
+        }
+        // BigTIFF: 64-bit offset at byte 8
+        Some([0, 43]) | Some([43, 0]) if data.len() >= 16 => {
+            let bytes = if big_endian { offset.to_be_bytes() } else { offset.to_le_bytes() };
+            page[8..16].copy_from_slice(&bytes);
+        }
+        _ => return Err(AppError::ImageReadError("Invalid TIFF header".to_string())),
+    }
+
+    Ok(page)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use image::codecs::gif::GifEncoder;
+    use image::{Frame, Rgba, RgbaImage};
+
+    /// Animated GIF whose frames are filled with the given grey levels
+    pub(crate) fn animated_gif(levels: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        {
+            let mut encoder = GifEncoder::new(&mut data);
+            for &level in levels {
+                let frame = RgbaImage::from_pixel(4, 4, Rgba([level, level, level, 255]));
+                encoder.encode_frame(Frame::new(frame)).unwrap();
+            }
+        }
+        data
+    }
+
+    /// Multi-page TIFF with one page per (width, height)
+    pub(crate) fn multi_page_tiff(sizes: &[(u32, u32)]) -> Vec<u8> {
+        let mut data = Cursor::new(Vec::new());
+        {
+            let mut encoder = tiff::encoder::TiffEncoder::new(&mut data).unwrap();
+            for &(width, height) in sizes {
+                let pixels = vec![128u8; (width * height) as usize];
+                encoder
+                    .write_image::<tiff::encoder::colortype::Gray8>(width, height, &pixels)
+                    .unwrap();
+            }
+        }
+        data.into_inner()
+    }
+
+    #[test]
+    fn test_select_frames() {
+        assert_eq!(select_frames(&FrameSelection::All, 3).unwrap(), 0..3);
+        assert_eq!(select_frames(&FrameSelection::First, 3).unwrap(), 0..1);
+        assert_eq!(select_frames(&FrameSelection::Range { start: 2, end: 9 }, 3).unwrap(), 1..3);
+        assert!(matches!(
+            select_frames(&FrameSelection::Range { start: 4, end: 5 }, 3),
+            Err(AppError::InvalidSettings(_))
+        ));
+    }
+
+    #[test]
+    fn test_gif_frames() {
+        let gif = animated_gif(&[0, 100, 200]);
+        assert_eq!(frame_count(&gif, ImageFormat::Gif).unwrap(), 3);
+
+        let mut levels = Vec::new();
+        let range = select_frames(&FrameSelection::Range { start: 2, end: 3 }, 3).unwrap();
+        for_each_frame(&gif, ImageFormat::Gif, range, |frame| {
+            levels.push(frame.to_rgba8().get_pixel(0, 0).0[0]);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(levels, vec![100, 200]);
+    }
+
+    #[test]
+    fn test_count_frames_without_decoding() {
+        // Frames whose pixel data is garbage are counted all the same
+        let mut gif = animated_gif(&[0, 100]);
+        let image_data = gif.len() - 4;
+        gif[image_data] ^= 0xFF;
+        assert_eq!(frame_count(&gif, ImageFormat::Gif).unwrap(), 2);
+        assert!(frame_count(b"GIF89a\x01\x00\x01\x00\x00\x00\x00\x99", ImageFormat::Gif).is_err());
+
+        let chunk = |fourcc: &[u8; 4], payload: &[u8]| {
+            let mut data = fourcc.to_vec();
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(payload);
+            if payload.len() % 2 == 1 {
+                data.push(0);
+            }
+            data
+        };
+        let webp = |chunks: &[Vec<u8>]| {
+            let body: Vec<u8> = [b"WEBP".to_vec(), chunks.concat()].concat();
+            [b"RIFF".to_vec(), (body.len() as u32).to_le_bytes().to_vec(), body].concat()
+        };
+        let animated = webp(&[
+            chunk(b"VP8X", &[0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            chunk(b"ANIM", &[0; 6]),
+            chunk(b"ANMF", &[0; 3]),
+            chunk(b"ANMF", &[0; 4]),
+            chunk(b"ANMF", &[0; 5]),
+        ]);
+        assert_eq!(frame_count(&animated, ImageFormat::WebP).unwrap(), 3);
+        let still = webp(&[chunk(b"VP8L", &[0; 5])]);
+        assert_eq!(frame_count(&still, ImageFormat::WebP).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_tiff_pages() {
+        let tiff = multi_page_tiff(&[(4, 2), (6, 3)]);
+        let offsets = tiff_page_offsets(&tiff).unwrap();
+        assert_eq!(offsets.len(), 2);
+        assert_eq!(frame_count(&tiff, ImageFormat::Tiff).unwrap(), 2);
+
+        let second = image::load_from_memory_with_format(
+            &tiff_page(&tiff, offsets[1]).unwrap(),
+            ImageFormat::Tiff,
+        )
+        .unwrap();
+        assert_eq!((second.width(), second.height()), (6, 3));
+    }
+}
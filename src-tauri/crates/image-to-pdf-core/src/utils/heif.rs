@@ -0,0 +1,86 @@
+use crate::error::{AppError, Result};
+use image::DynamicImage;
+
+#[cfg(feature = "heif")]
+use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+/// A decoded HEIF or AVIF image
+pub struct HeifImage {
+    /// Upright pixels
+    pub image: DynamicImage,
+    /// ICC profile stored with the image, if any
+    pub icc_profile: Option<Vec<u8>>,
+}
+
+/// Error for HEIF input in a build without the `heif` feature
+pub fn missing_decoder(name: &str) -> AppError {
+    AppError::UnsupportedFormat(format!("{} (this build has no HEIF decoder)", name))
+}
+
+/// Size of the primary image, after its rotation
+#[cfg(feature = "heif")]
+pub fn heif_dimensions(data: &[u8]) -> Result<(u32, u32)> {
+    let context = HeifContext::read_from_bytes(data).map_err(read_error)?;
+    let handle = context.primary_image_handle().map_err(read_error)?;
+    Ok((handle.width(), handle.height()))
+}
+
+/// Decode the primary image of a HEIF or AVIF file
+///
+/// libheif applies the rotation and mirroring stored in the file while
+/// decoding. The EXIF orientation of such files repeats those, so the pixels
+/// are already upright and must not be turned again.
+#[cfg(feature = "heif")]
+pub fn decode_heif(data: &[u8]) -> Result<HeifImage> {
+    let context = HeifContext::read_from_bytes(data).map_err(read_error)?;
+    let handle = context.primary_image_handle().map_err(read_error)?;
+    let has_alpha = handle.has_alpha_channel();
+    let chroma = if has_alpha {
+        RgbChroma::Rgba
+    } else {
+        RgbChroma::Rgb
+    };
+
+    let decoded = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(chroma), None)
+        .map_err(read_error)?;
+    let planes = decoded.planes();
+    let plane = planes
+        .interleaved
+        .ok_or_else(|| AppError::ImageReadError("HEIF image has no RGB samples".to_string()))?;
+
+    // Rows are padded to the plane stride
+    let (width, height) = (plane.width, plane.height);
+    let row_len = width as usize * if has_alpha { 4 } else { 3 };
+    let mut samples = Vec::with_capacity(row_len * height as usize);
+    for row in plane.data.chunks(plane.stride).take(height as usize) {
+        samples.extend_from_slice(&row[..row_len]);
+    }
+
+    let image = if has_alpha {
+        image::RgbaImage::from_raw(width, height, samples).map(DynamicImage::ImageRgba8)
+    } else {
+        image::RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8)
+    }
+    .ok_or_else(|| AppError::ImageReadError("HEIF image is truncated".to_string()))?;
+
+    Ok(HeifImage {
+        image,
+        icc_profile: handle.color_profile_raw().map(|profile| profile.data),
+    })
+}
+
+#[cfg(feature = "heif")]
+fn read_error(err: libheif_rs::HeifError) -> AppError {
+    AppError::ImageReadError(format!("Failed to read HEIF image: {}", err))
+}
+
+#[cfg(not(feature = "heif"))]
+pub fn heif_dimensions(_data: &[u8]) -> Result<(u32, u32)> {
+    Err(missing_decoder("HEIF"))
+}
+
+#[cfg(not(feature = "heif"))]
+pub fn decode_heif(_data: &[u8]) -> Result<HeifImage> {
+    Err(missing_decoder("HEIF"))
+}
@@ -0,0 +1,244 @@
+use crate::error::{AppError, Result};
+use crate::utils::dpi::{tiff_dpi, Dpi};
+
+/// Header information needed to embed a JPEG without decoding it
+#[derive(Debug, Clone, PartialEq)]
+pub struct JpegInfo {
+    pub width: u32,
+    pub height: u32,
+    pub components: u8,
+    pub bits_per_component: u8,
+    /// Transform flag from the Adobe APP14 segment, if present
+    pub adobe_transform: Option<u8>,
+    /// ICC profile reassembled from APP2 `ICC_PROFILE` chunks
+    pub icc_profile: Option<Vec<u8>>,
+    /// Resolution from the JFIF APP0 segment, or the EXIF APP1 segment
+    pub dpi: Option<Dpi>,
+}
+
+impl JpegInfo {
+    /// PDF device colour space matching the component count
+    pub fn device_color_space(&self) -> &'static str {
+        match self.components {
+            1 => "DeviceGray",
+            4 => "DeviceCMYK",
+            _ => "DeviceRGB",
+        }
+    }
+
+    /// Adobe CMYK JPEGs store inverted samples and need a `/Decode` array
+    pub fn is_inverted_cmyk(&self) -> bool {
+        self.components == 4 && self.adobe_transform.is_some()
+    }
+}
+
+const ICC_MARKER: &[u8] = b"ICC_PROFILE\0";
+const ADOBE_MARKER: &[u8] = b"Adobe";
+const JFIF_MARKER: &[u8] = b"JFIF\0";
+const EXIF_MARKER: &[u8] = b"Exif\0\0";
+
+/// Parse SOF, APP0 (JFIF), APP1 (EXIF), APP14 (Adobe) and APP2 (ICC) segments of a JPEG file
+///
+/// Scanning stops at the first SOS marker, so only the header is read.
+pub fn parse_jpeg_header(data: &[u8]) -> Result<JpegInfo> {
+    let invalid = |reason: &str| AppError::ImageReadError(format!("Invalid JPEG: {}", reason));
+
+    if data.len() < 4 || data[0] != 0xFF || data[1] != 0xD8 {
+        return Err(invalid("missing SOI marker"));
+    }
+
+    let mut frame: Option<(u32, u32, u8, u8)> = None;
+    let mut adobe_transform = None;
+    let mut icc_chunks: Vec<(u8, Vec<u8>)> = Vec::new();
+    let (mut jfif_dpi, mut exif_dpi) = (None, None);
+    let mut pos = 2;
+
+    while pos + 4 <= data.len() {
+        if data[pos] != 0xFF {
+            return Err(invalid("expected marker"));
+        }
+        let marker = data[pos + 1];
+
+        // Fill bytes and standalone markers carry no length
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+        if marker == 0xD9 || marker == 0xDA {
+            break;
+        }
+
+        let length = u16::from_be_bytes([data[pos + 2], data[pos + 3]]) as usize;
+        if length < 2 || pos + 2 + length > data.len() {
+            return Err(invalid("truncated segment"));
+        }
+        let segment = &data[pos + 4..pos + 2 + length];
+
+        match marker {
+            // SOF0..SOF15, excluding DHT (C4), JPG (C8) and DAC (CC)
+            0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                if segment.len() < 6 {
+                    return Err(invalid("truncated frame header"));
+                }
+                let bits = segment[0];
+                let height = u16::from_be_bytes([segment[1], segment[2]]) as u32;
+                let width = u16::from_be_bytes([segment[3], segment[4]]) as u32;
+                frame = Some((width, height, segment[5], bits));
+            }
+            0xE0 if segment.starts_with(JFIF_MARKER) && segment.len() >= 12 => {
+                let x = u16::from_be_bytes([segment[8], segment[9]]) as f32;
+                let y = u16::from_be_bytes([segment[10], segment[11]]) as f32;
+                jfif_dpi = jfif_dpi.or(Dpi::from_density(x, y, segment[7]));
+            }
+            0xE1 if segment.starts_with(EXIF_MARKER) => {
+                exif_dpi = exif_dpi.or(tiff_dpi(&segment[EXIF_MARKER.len()..]));
+            }
+            0xE2 if segment.starts_with(ICC_MARKER) && segment.len() > ICC_MARKER.len() + 2 => {
+                let sequence = segment[ICC_MARKER.len()];
+                icc_chunks.push((sequence, segment[ICC_MARKER.len() + 2..].to_vec()));
+            }
+            0xEE if segment.starts_with(ADOBE_MARKER) && segment.len() >= 12 => {
+                adobe_transform = Some(segment[11]);
+            }
+            _ => {}
+        }
+
+        pos += 2 + length;
+    }
+
+    let (width, height, components, bits_per_component) =
+        frame.ok_or_else(|| invalid("missing frame header"))?;
+
+    if !matches!(components, 1 | 3 | 4) {
+        return Err(AppError::UnsupportedFormat(format!(
+            "JPEG with {} color components",
+            components
+        )));
+    }
+
+    let icc_profile = if icc_chunks.is_empty() {
+        None
+    } else {
+        icc_chunks.sort_by_key(|(sequence, _)| *sequence);
+        Some(icc_chunks.into_iter().flat_map(|(_, chunk)| chunk).collect())
+    };
+
+    Ok(JpegInfo {
+        width,
+        height,
+        components,
+        bits_per_component,
+        adobe_transform,
+        icc_profile,
+        dpi: jfif_dpi.or(exif_dpi),
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn encode_jpeg(img: image::DynamicImage) -> Vec<u8> {
+        let mut data = Vec::new();
+        img.write_to(&mut std::io::Cursor::new(&mut data), image::ImageFormat::Jpeg)
+            .unwrap();
+        data
+    }
+
+    /// Adobe CMYK JPEG of one 8x8 block per component, all at mid grey
+    pub(crate) fn cmyk_jpeg() -> Vec<u8> {
+        let segment = |marker: u8, payload: &[u8]| {
+            let mut data = vec![0xFF, marker];
+            data.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+            data.extend_from_slice(payload);
+            data
+        };
+        // Huffman tables holding a single one-bit code for symbol 0
+        let table = |class: u8| {
+            let mut payload = vec![class << 4, 1];
+            payload.extend_from_slice(&[0; 15]);
+            payload.push(0);
+            payload
+        };
+
+        let mut data = vec![0xFF, 0xD8];
+        data.extend(segment(0xEE, b"Adobe\0\x64\0\0\0\0\0"));
+        data.extend(segment(0xDB, &[[0u8].as_slice(), &[1; 64]].concat()));
+        let frame = [8, 0, 8, 0, 8, 4, 1, 0x11, 0, 2, 0x11, 0, 3, 0x11, 0, 4, 0x11, 0];
+        data.extend(segment(0xC0, &frame));
+        data.extend(segment(0xC4, &table(0)));
+        data.extend(segment(0xC4, &table(1)));
+        data.extend(segment(0xDA, &[4, 1, 0, 2, 0, 3, 0, 4, 0, 0, 63, 0]));
+        // Each block is a zero DC difference followed by end-of-block
+        data.extend_from_slice(&[0x00, 0xFF, 0xD9]);
+        data
+    }
+
+    /// Insert a raw marker segment right after SOI
+    pub(crate) fn insert_segment(jpeg: &[u8], marker: u8, payload: &[u8]) -> Vec<u8> {
+        let length = (payload.len() + 2) as u16;
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xFF, marker]);
+        data.extend_from_slice(&length.to_be_bytes());
+        data.extend_from_slice(payload);
+        data.extend_from_slice(&jpeg[2..]);
+        data
+    }
+
+    #[test]
+    fn test_parse_rgb_and_gray_jpeg() {
+        let rgb = encode_jpeg(image::DynamicImage::new_rgb8(16, 8));
+        let info = parse_jpeg_header(&rgb).unwrap();
+        assert_eq!((info.width, info.height, info.components), (16, 8, 3));
+        assert_eq!(info.device_color_space(), "DeviceRGB");
+
+        let gray = encode_jpeg(image::DynamicImage::new_luma8(4, 4));
+        let info = parse_jpeg_header(&gray).unwrap();
+        assert_eq!(info.components, 1);
+        assert_eq!(info.device_color_space(), "DeviceGray");
+    }
+
+    #[test]
+    fn test_parse_adobe_and_icc_segments() {
+        let jpeg = encode_jpeg(image::DynamicImage::new_rgb8(4, 4));
+
+        let mut adobe = b"Adobe".to_vec();
+        adobe.extend_from_slice(&[0, 100, 0, 0, 0, 0, 1]);
+        let jpeg = insert_segment(&jpeg, 0xEE, &adobe);
+
+        // Chunks deliberately out of order
+        let mut second = ICC_MARKER.to_vec();
+        second.extend_from_slice(&[2, 2, b'C', b'D']);
+        let mut first = ICC_MARKER.to_vec();
+        first.extend_from_slice(&[1, 2, b'A', b'B']);
+        let jpeg = insert_segment(&insert_segment(&jpeg, 0xE2, &second), 0xE2, &first);
+
+        let info = parse_jpeg_header(&jpeg).unwrap();
+        assert_eq!(info.adobe_transform, Some(1));
+        assert_eq!(info.icc_profile.as_deref(), Some(&b"ABCD"[..]));
+    }
+
+    #[test]
+    fn test_parse_jfif_and_exif_resolution() {
+        let jpeg = encode_jpeg(image::DynamicImage::new_rgb8(4, 4));
+
+        let mut exif = EXIF_MARKER.to_vec();
+        exif.extend_from_slice(&crate::utils::dpi::tests::tiff_resolution(300, 2));
+        let tagged = insert_segment(&jpeg, 0xE1, &exif);
+        assert_eq!(parse_jpeg_header(&tagged).unwrap().dpi, Some(Dpi { x: 300.0, y: 300.0 }));
+
+        // JFIF density in dots per centimetre takes precedence
+        let jfif = [b'J', b'F', b'I', b'F', 0, 1, 2, 2, 0, 100, 0, 100, 0, 0];
+        let tagged = insert_segment(&tagged, 0xE0, &jfif);
+        assert_eq!(parse_jpeg_header(&tagged).unwrap().dpi, Some(Dpi { x: 254.0, y: 254.0 }));
+    }
+
+    #[test]
+    fn test_parse_rejects_non_jpeg() {
+        assert!(parse_jpeg_header(b"\x89PNG\r\n\x1a\n").is_err());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod validation;
+pub mod optimize;
+pub mod jpeg;
+pub mod orientation;
+pub mod dpi;
+pub mod frames;
+pub mod heif;
+pub mod svg;
+pub mod pdf;
@@ -1,5 +1,7 @@
 use crate::error::AppError;
-use image::ImageFormat;
+use crate::utils::jpeg::parse_jpeg_header;
+use crate::utils::validation::{validate_image_format, InputFormat};
+use image::{ImageEncoder, ImageFormat};
 use std::path::Path;
 use std::fs;
 
@@ -17,6 +19,17 @@ pub fn optimize_png(input_path: &Path, output_path: &Path) -> Result<(), AppErro
     let input_data = fs::read(input_path)
         .map_err(|e| AppError::IoError(format!("Failed to read PNG file: {}", e)))?;
 
+    let optimized_data = optimize_png_data(&input_data)?;
+
+    // Write optimized PNG to output
+    fs::write(output_path, optimized_data)
+        .map_err(|e| AppError::IoError(format!("Failed to write optimized PNG: {}", e)))?;
+
+    Ok(())
+}
+
+/// Optimize in-memory PNG data using oxipng
+pub fn optimize_png_data(data: &[u8]) -> Result<Vec<u8>, AppError> {
     // Configure oxipng options
     let options = oxipng::Options {
         strip: oxipng::StripChunks::Safe, // Remove unnecessary chunks
@@ -24,14 +37,8 @@ pub fn optimize_png(input_path: &Path, output_path: &Path) -> Result<(), AppErro
     };
 
     // Optimize the PNG
-    let optimized_data = oxipng::optimize_from_memory(&input_data, &options)
-        .map_err(|e| AppError::ImageProcessingError(format!("PNG optimization failed: {}", e)))?;
-
-    // Write optimized PNG to output
-    fs::write(output_path, optimized_data)
-        .map_err(|e| AppError::IoError(format!("Failed to write optimized PNG: {}", e)))?;
-
-    Ok(())
+    oxipng::optimize_from_memory(data, &options)
+        .map_err(|e| AppError::ImageProcessingError(format!("PNG optimization failed: {}", e)))
 }
 
 /// Optimize JPEG image by re-encoding with specified quality
@@ -48,8 +55,10 @@ pub fn optimize_jpeg(input_path: &Path, output_path: &Path, quality: u8) -> Resu
     // Ensure quality is in valid range
     let quality = quality.clamp(1, 100);
 
-    // Load the image
-    let img = image::open(input_path)
+    // Load the image, recognizing its format from the content
+    let img = image::ImageReader::open(input_path)?
+        .with_guessed_format()?
+        .decode()
         .map_err(|e| AppError::ImageReadError(format!("Failed to read JPEG: {}", e)))?;
 
     // Create JPEG encoder with specified quality
@@ -65,6 +74,33 @@ pub fn optimize_jpeg(input_path: &Path, output_path: &Path, quality: u8) -> Resu
     Ok(())
 }
 
+/// Re-encode in-memory JPEG data with the specified quality
+///
+/// Returns `None` for CMYK and Adobe JPEGs, which would be decoded to RGB
+/// and lose their colour space. An ICC profile is copied to the output.
+pub fn optimize_jpeg_data(data: &[u8], quality: u8) -> Result<Option<Vec<u8>>, AppError> {
+    let info = parse_jpeg_header(data)?;
+    if info.components == 4 || info.adobe_transform.is_some() {
+        return Ok(None);
+    }
+
+    let img = image::load_from_memory(data)
+        .map_err(|e| AppError::ImageReadError(format!("Failed to read JPEG: {}", e)))?;
+
+    let mut output = Vec::new();
+    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output, quality.clamp(1, 100));
+    if let Some(profile) = info.icc_profile {
+        encoder
+            .set_icc_profile(profile)
+            .map_err(|e| AppError::ImageProcessingError(format!("JPEG encoding failed: {}", e)))?;
+    }
+
+    img.write_with_encoder(encoder)
+        .map_err(|e| AppError::ImageProcessingError(format!("JPEG encoding failed: {}", e)))?;
+
+    Ok(Some(output))
+}
+
 /// Optimize an image based on its format
 ///
 /// # Arguments
@@ -80,13 +116,14 @@ pub fn optimize_image(
     output_path: &Path,
     jpeg_quality: u8,
 ) -> Result<(), AppError> {
-    // Detect image format
-    let format = image::ImageFormat::from_path(input_path)
-        .map_err(|e| AppError::UnsupportedFormat(format!("Cannot detect image format: {}", e)))?;
+    // Detect image format from the content
+    let format = validate_image_format(&input_path.to_string_lossy())?;
 
     match format {
-        ImageFormat::Png => optimize_png(input_path, output_path),
-        ImageFormat::Jpeg => optimize_jpeg(input_path, output_path, jpeg_quality),
+        InputFormat::Image(ImageFormat::Png) => optimize_png(input_path, output_path),
+        InputFormat::Image(ImageFormat::Jpeg) => {
+            optimize_jpeg(input_path, output_path, jpeg_quality)
+        }
         // For other formats, just copy the file without optimization
         _ => {
             fs::copy(input_path, output_path)
@@ -96,6 +133,22 @@ pub fn optimize_image(
     }
 }
 
+/// Optimize in-memory image data based on its format
+///
+/// Returns `None` for formats that are embedded without optimization, and
+/// for JPEGs whose colour space re-encoding would not keep.
+pub fn optimize_image_data(
+    data: &[u8],
+    format: ImageFormat,
+    jpeg_quality: u8,
+) -> Result<Option<Vec<u8>>, AppError> {
+    match format {
+        ImageFormat::Png => optimize_png_data(data).map(Some),
+        ImageFormat::Jpeg => optimize_jpeg_data(data, jpeg_quality),
+        _ => Ok(None),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,96 @@
+use crate::error::Result;
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use std::io::Cursor;
+
+/// Read the EXIF orientation of an in-memory image
+///
+/// Any format whose decoder exposes EXIF (JPEG, TIFF, WebP, PNG, HEIF-style
+/// containers) is supported. Missing or unreadable tags mean no transform.
+pub fn read_orientation(data: &[u8], format: ImageFormat) -> Orientation {
+    ImageReader::with_format(Cursor::new(data), format)
+        .into_decoder()
+        .and_then(|mut decoder| decoder.orientation())
+        .unwrap_or(Orientation::NoTransforms)
+}
+
+/// Whether the orientation turns the image by a quarter, swapping width and height
+pub fn swaps_dimensions(orientation: Orientation) -> bool {
+    matches!(
+        orientation,
+        Orientation::Rotate90
+            | Orientation::Rotate270
+            | Orientation::Rotate90FlipH
+            | Orientation::Rotate270FlipH
+    )
+}
+
+/// Width and height of the image as it should be displayed
+pub fn oriented_dimensions(width: u32, height: u32, orientation: Orientation) -> (u32, u32) {
+    if swaps_dimensions(orientation) {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+/// Decode an image and rotate its pixels upright according to its EXIF orientation
+pub fn load_oriented_image(data: &[u8], format: ImageFormat) -> Result<DynamicImage> {
+    let mut decoder = ImageReader::with_format(Cursor::new(data), format).into_decoder()?;
+    let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+
+    let mut img = DynamicImage::from_decoder(decoder)?;
+    img.apply_orientation(orientation);
+    Ok(img)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Big-endian EXIF APP1 payload holding only an Orientation tag
+    pub(crate) fn exif_orientation_segment(value: u16) -> Vec<u8> {
+        let mut data = b"Exif\0\0MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        data.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+        data.extend_from_slice(&value.to_be_bytes());
+        data.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        data
+    }
+
+    /// Encode a JPEG and tag it with the given EXIF orientation
+    pub(crate) fn oriented_jpeg(width: u32, height: u32, orientation: u16) -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        DynamicImage::new_rgb8(width, height)
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)
+            .unwrap();
+
+        let payload = exif_orientation_segment(orientation);
+        let mut data = jpeg[..2].to_vec();
+        data.extend_from_slice(&[0xFF, 0xE1]);
+        data.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        data.extend_from_slice(&payload);
+        data.extend_from_slice(&jpeg[2..]);
+        data
+    }
+
+    #[test]
+    fn test_read_orientation_from_jpeg_exif() {
+        let jpeg = oriented_jpeg(16, 8, 6);
+        assert_eq!(read_orientation(&jpeg, ImageFormat::Jpeg), Orientation::Rotate90);
+
+        let img = load_oriented_image(&jpeg, ImageFormat::Jpeg).unwrap();
+        assert_eq!((img.width(), img.height()), (8, 16));
+    }
+
+    #[test]
+    fn test_missing_orientation_is_identity() {
+        let mut png = Vec::new();
+        DynamicImage::new_rgb8(4, 2)
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        assert_eq!(read_orientation(&png, ImageFormat::Png), Orientation::NoTransforms);
+        assert_eq!(oriented_dimensions(4, 2, Orientation::Rotate270), (2, 4));
+        assert_eq!(oriented_dimensions(4, 2, Orientation::FlipVertical), (4, 2));
+    }
+}
@@ -0,0 +1,116 @@
+use crate::error::{AppError, Result};
+use crate::import::{inherited_attribute, page_box};
+use lopdf::Document;
+
+/// Pages selected from a PDF input, parsed from ranges like `1-3, 5, 8-`
+///
+/// Pages are counted from 1 and added in the order the ranges are listed; an
+/// open range runs to the last page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageRanges(Vec<(u32, Option<u32>)>);
+
+impl PageRanges {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = || AppError::InvalidSettings(format!("Invalid page range: {}", spec));
+        let number = |part: &str| match part.trim().parse::<u32>() {
+            Ok(0) | Err(_) => Err(invalid()),
+            Ok(n) => Ok(n),
+        };
+
+        let ranges = spec
+            .split(',')
+            .map(|range| match range.split_once('-') {
+                Some((start, end)) if end.trim().is_empty() => Ok((number(start)?, None)),
+                Some((start, end)) => {
+                    let (start, end) = (number(start)?, number(end)?);
+                    if end < start {
+                        return Err(invalid());
+                    }
+                    Ok((start, Some(end)))
+                }
+                None => {
+                    let page = number(range)?;
+                    Ok((page, Some(page)))
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(PageRanges(ranges))
+    }
+
+    /// Zero-based indices of the selected pages out of `count`
+    ///
+    /// Fails when a range starts after the last page.
+    pub fn select(&self, count: usize) -> Result<Vec<usize>> {
+        let mut pages = Vec::new();
+        for &(start, end) in &self.0 {
+            let (start, end) = (start as usize, end.map_or(count, |end| end as usize));
+            if start > count {
+                return Err(AppError::InvalidSettings(format!(
+                    "Page {} is outside the document's {} pages",
+                    start, count
+                )));
+            }
+            pages.extend(start - 1..end.min(count));
+        }
+        Ok(pages)
+    }
+}
+
+/// Parse a PDF input, opening documents encrypted without an open password
+pub fn load_pdf(data: &[u8]) -> Result<Document> {
+    let mut doc = Document::load_mem(data)
+        .map_err(|e| AppError::ImageReadError(format!("Failed to read PDF: {}", e)))?;
+    if doc.is_encrypted() && doc.decrypt("").is_err() {
+        return Err(AppError::ImageReadError(
+            "PDF is protected by a password".to_string(),
+        ));
+    }
+    if doc.get_pages().is_empty() {
+        return Err(AppError::ImageReadError("PDF has no pages".to_string()));
+    }
+    Ok(doc)
+}
+
+/// Number of pages and the size of the first page as displayed, in points
+pub fn pdf_summary(doc: &Document) -> Result<(usize, (f32, f32))> {
+    let pages = doc.get_pages();
+    let first = pages
+        .values()
+        .next()
+        .and_then(|&id| doc.get_dictionary(id).ok())
+        .ok_or_else(|| AppError::ImageReadError("PDF has no pages".to_string()))?;
+
+    let [x0, y0, x1, y1] = page_box(doc, first)?;
+    let rotate = inherited_attribute(doc, first, b"Rotate")
+        .and_then(|rotate| rotate.as_i64().ok())
+        .unwrap_or(0);
+    let size = if rotate.rem_euclid(180) == 90 {
+        (y1 - y0, x1 - x0)
+    } else {
+        (x1 - x0, y1 - y0)
+    };
+
+    Ok((pages.len(), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_ranges() {
+        let ranges = PageRanges::parse("3-4, 1, 6-").unwrap();
+        assert_eq!(ranges.select(8).unwrap(), vec![2, 3, 0, 5, 6, 7]);
+        // Ranges past the end are cut short
+        assert_eq!(
+            PageRanges::parse("2-9").unwrap().select(3).unwrap(),
+            vec![1, 2]
+        );
+        assert!(PageRanges::parse("5").unwrap().select(3).is_err());
+
+        for invalid in ["", "0", "4-2", "1,,2", "a-b", "-3"] {
+            assert!(PageRanges::parse(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+}
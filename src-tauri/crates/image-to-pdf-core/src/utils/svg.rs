@@ -0,0 +1,74 @@
+use crate::error::{AppError, Result};
+use image::{DynamicImage, RgbaImage};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{fontdb, PostProcessingSteps, Tree, TreeParsing, TreePostProc};
+use std::sync::OnceLock;
+
+/// CSS pixels per inch, the unit of SVG lengths without one
+pub const SVG_DPI: f32 = 96.0;
+
+/// System fonts for drawing SVG text, loaded on first use
+fn fonts() -> &'static fontdb::Database {
+    static FONTS: OnceLock<fontdb::Database> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        let mut fonts = fontdb::Database::new();
+        fonts.load_system_fonts();
+        fonts
+    })
+}
+
+/// Parse an SVG document, turning its text into outlines
+///
+/// Text is drawn with the fonts installed on this system, so the output does
+/// not depend on fonts being available to the PDF reader.
+pub fn parse_svg(data: &[u8]) -> Result<Tree> {
+    let mut tree = Tree::from_data(data, &Default::default())
+        .map_err(|e| AppError::ImageReadError(format!("Failed to read SVG: {}", e)))?;
+    tree.postprocess(PostProcessingSteps::default(), fonts());
+    Ok(tree)
+}
+
+/// Width and height of the box the viewBox is drawn into, in CSS pixels
+pub fn svg_size(tree: &Tree) -> (f32, f32) {
+    (tree.size.width(), tree.size.height())
+}
+
+/// Convert an SVG document to a one-page PDF whose page is its viewport, in
+/// points at 96 pixels per inch
+pub fn svg_to_pdf(tree: &Tree) -> Vec<u8> {
+    svg2pdf::convert_tree(
+        tree,
+        svg2pdf::Options {
+            dpi: SVG_DPI,
+            ..Default::default()
+        },
+    )
+}
+
+/// Rasterize an SVG document so that it fits in a `size` x `size` box
+pub fn render_svg(tree: &Tree, size: u32) -> Result<DynamicImage> {
+    let (width, height) = svg_size(tree);
+    let scale = size as f32 / width.max(height);
+    let pixels = |length: f32| ((length * scale).round() as u32).max(1);
+
+    let mut pixmap = Pixmap::new(pixels(width), pixels(height))
+        .ok_or_else(|| AppError::ImageProcessingError("SVG is too large to render".to_string()))?;
+    resvg::render(
+        tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+
+    // tiny-skia keeps premultiplied alpha
+    let samples = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(pixmap.width(), pixmap.height(), samples)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| AppError::ImageProcessingError("Failed to render SVG".to_string()))
+}
@@ -0,0 +1,326 @@
+use crate::error::{AppError, Result};
+use crate::utils::heif::missing_decoder;
+use image::ImageFormat;
+use std::io::Read;
+use std::path::Path;
+
+/// Maximum file size in bytes (50 MB)
+const MAX_FILE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Supported image formats
+const SUPPORTED_FORMATS: &[ImageFormat] = &[
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::WebP,
+    ImageFormat::Bmp,
+    ImageFormat::Gif,
+    ImageFormat::Tiff,
+];
+
+/// Bytes read from the start of a file to recognize its format, enough to
+/// get past the XML declaration and comments that precede an `<svg>` element
+const SIGNATURE_LEN: u64 = 1024;
+
+/// Format of an input file, recognized from its content
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputFormat {
+    /// Decoded by the `image` crate
+    Image(ImageFormat),
+    /// HEIF with HEVC-coded images, as saved by phones
+    Heic,
+    /// HEIF with AV1-coded images
+    Avif,
+    /// SVG drawing, embedded as vector content
+    Svg,
+    /// PDF document, whose pages are copied into the output
+    Pdf,
+}
+
+impl InputFormat {
+    /// Upper-case name as shown to users, e.g. `JPG`
+    pub fn name(&self) -> String {
+        match self {
+            InputFormat::Image(format) => format.extensions_str()[0].to_uppercase(),
+            InputFormat::Heic => "HEIC".to_string(),
+            InputFormat::Avif => "AVIF".to_string(),
+            InputFormat::Svg => "SVG".to_string(),
+            InputFormat::Pdf => "PDF".to_string(),
+        }
+    }
+
+    /// Format an extension claims, if it names one
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "heic" => Some(InputFormat::Heic),
+            "avif" => Some(InputFormat::Avif),
+            "svg" => Some(InputFormat::Svg),
+            "pdf" => Some(InputFormat::Pdf),
+            extension => ImageFormat::from_extension(extension).map(InputFormat::Image),
+        }
+    }
+}
+
+/// Detect a supported image format from the leading bytes of its content
+pub fn sniff_format(data: &[u8]) -> Result<InputFormat> {
+    if let Some(format) = sniff_heif(data) {
+        if !cfg!(feature = "heif") {
+            return Err(missing_decoder(&format.name()));
+        }
+        return Ok(format);
+    }
+    if is_svg(data) {
+        return Ok(InputFormat::Svg);
+    }
+
+    // Image magic comes first, since compressed data may contain `%PDF-`
+    if let Ok(format) = image::guess_format(data) {
+        if !SUPPORTED_FORMATS.contains(&format) {
+            return Err(AppError::UnsupportedFormat(format!(
+                "{} ({})",
+                InputFormat::Image(format).name(),
+                supported_names()
+            )));
+        }
+        return Ok(InputFormat::Image(format));
+    }
+
+    // Readers accept a header anywhere in the first kilobyte
+    let header = &data[..data.len().min(SIGNATURE_LEN as usize)];
+    if header.windows(5).any(|window| window == b"%PDF-") {
+        return Ok(InputFormat::Pdf);
+    }
+
+    Err(AppError::UnsupportedFormat(format!(
+        "unrecognized content ({})",
+        supported_names()
+    )))
+}
+
+/// Recognize the `ftyp` box of a HEIF file by its major or compatible brands
+fn sniff_heif(data: &[u8]) -> Option<InputFormat> {
+    if data.len() < 16 || &data[4..8] != b"ftyp" {
+        return None;
+    }
+
+    // Major brand, then compatible brands after the minor version
+    let box_len = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    let brands = std::iter::once(&data[8..12])
+        .chain(data[16..box_len.clamp(16, data.len())].chunks_exact(4));
+    let mut heif = false;
+    for brand in brands {
+        match brand {
+            b"avif" | b"avis" => return Some(InputFormat::Avif),
+            b"heic" | b"heix" | b"hevc" | b"hevx" | b"heim" | b"heis" | b"mif1" | b"msf1" => {
+                heif = true
+            }
+            _ => {}
+        }
+    }
+    heif.then_some(InputFormat::Heic)
+}
+
+/// Recognize SVG markup: text that starts with a tag and has an `<svg`
+/// element in the first `SIGNATURE_LEN` bytes, however much data is given
+fn is_svg(data: &[u8]) -> bool {
+    let data = &data[..data.len().min(SIGNATURE_LEN as usize)];
+    let text = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let text = &text[text.iter().take_while(|b| b.is_ascii_whitespace()).count()..];
+    text.starts_with(b"<") && text.windows(4).any(|window| window == b"<svg")
+}
+
+/// Validate image file format by content
+///
+/// Files without an extension, or with one that names no image format, are
+/// accepted by content alone. An extension naming a different image format
+/// than the content is an error, so a PNG saved as `.jpg` is never decoded
+/// or embedded as a JPEG.
+pub fn validate_image_format(path: &str) -> Result<InputFormat> {
+    let mut signature = Vec::new();
+    std::fs::File::open(path)?
+        .take(SIGNATURE_LEN)
+        .read_to_end(&mut signature)?;
+    let format = sniff_format(&signature)?;
+
+    let extension = Path::new(path).extension().and_then(|ext| ext.to_str());
+    if let Some(named) = extension.and_then(InputFormat::from_extension) {
+        if named != format {
+            return Err(AppError::FormatMismatch(format!(
+                "{} contains {} data, not {}",
+                Path::new(path)
+                    .file_name()
+                    .map_or(path.into(), |name| name.to_string_lossy()),
+                format.name(),
+                named.name()
+            )));
+        }
+    }
+
+    Ok(format)
+}
+
+fn supported_names() -> String {
+    let mut names: Vec<_> = SUPPORTED_FORMATS
+        .iter()
+        .map(|&format| InputFormat::Image(format).name())
+        .collect();
+    if cfg!(feature = "heif") {
+        names.extend([InputFormat::Heic.name(), InputFormat::Avif.name()]);
+    }
+    names.extend([InputFormat::Svg.name(), InputFormat::Pdf.name()]);
+    format!("supported: {}", names.join(", "))
+}
+
+/// Validate file size (must be less than 50 MB)
+pub fn validate_file_size(path: &str) -> Result<()> {
+    let metadata = std::fs::metadata(path)?;
+    let size = metadata.len();
+
+    if size > MAX_FILE_SIZE {
+        return Err(AppError::ImageTooLarge(size));
+    }
+
+    Ok(())
+}
+
+/// Validate that file exists
+pub fn validate_file_exists(path: &str) -> Result<()> {
+    if !Path::new(path).exists() {
+        return Err(AppError::ImageNotFound(path.to_string()));
+    }
+    Ok(())
+}
+
+/// Validate all aspects of an image file, returning its format
+pub fn validate_image(path: &str) -> Result<InputFormat> {
+    validate_file_exists(path)?;
+    validate_file_size(path)?;
+    validate_image_format(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_image(name: &str, format: ImageFormat) -> String {
+        let path = std::env::temp_dir().join(name);
+        image::RgbImage::new(4, 4)
+            .save_with_format(&path, format)
+            .unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_validate_format_supported() {
+        let cases = [
+            ("test_validation.png", ImageFormat::Png),
+            ("test_validation.jpg", ImageFormat::Jpeg),
+            ("test_validation.JPEG", ImageFormat::Jpeg),
+            ("test_validation.webp", ImageFormat::WebP),
+            // Scanner output without an extension
+            ("test_validation_scan", ImageFormat::Tiff),
+        ];
+        for (name, format) in cases {
+            let path = write_image(name, format);
+            assert_eq!(
+                validate_image_format(&path).unwrap(),
+                InputFormat::Image(format)
+            );
+            std::fs::remove_file(path).ok();
+        }
+    }
+
+    #[test]
+    fn test_validate_format_unsupported() {
+        let path = std::env::temp_dir().join("test_validation.txt");
+        std::fs::write(&path, "not an image").unwrap();
+        let path = path.to_string_lossy().to_string();
+        assert!(matches!(
+            validate_image_format(&path),
+            Err(AppError::UnsupportedFormat(_))
+        ));
+        assert!(sniff_format(b"%!PS-Adobe-3.0").is_err());
+        assert_eq!(sniff_format(b"%PDF-1.7").unwrap(), InputFormat::Pdf);
+        let mut late_header = vec![b' '; SIGNATURE_LEN as usize];
+        late_header.extend_from_slice(b"%PDF-1.7");
+        assert!(sniff_format(&late_header).is_err());
+        assert!(validate_image_format("/path/to/missing.png").is_err());
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_sniff_image_containing_pdf_marker() {
+        let mut png = Vec::new();
+        image::RgbImage::new(4, 4)
+            .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+        // A text chunk quoting a PDF header, as metadata from a scan might
+        let mut chunk = 9u32.to_be_bytes().to_vec();
+        chunk.extend_from_slice(b"tEXtNote\0%PDF-");
+        chunk.extend_from_slice(&[0; 4]);
+        png.splice(33..33, chunk);
+
+        assert_eq!(
+            sniff_format(&png).unwrap(),
+            InputFormat::Image(ImageFormat::Png)
+        );
+    }
+
+    #[test]
+    fn test_validate_format_mismatch() {
+        let path = write_image("test_validation_mismatch.jpg", ImageFormat::Png);
+        match validate_image_format(&path) {
+            Err(AppError::FormatMismatch(message)) => assert_eq!(
+                message,
+                "test_validation_mismatch.jpg contains PNG data, not JPG"
+            ),
+            other => panic!("expected a mismatch, got {:?}", other),
+        }
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_sniff_svg() {
+        let inkscape = b"\xEF\xBB\xBF<?xml version=\"1.0\"?>\n<!-- Created with Inkscape -->\n<svg width=\"10\">";
+        assert_eq!(sniff_format(inkscape).unwrap(), InputFormat::Svg);
+        assert_eq!(sniff_format(b"  <svg/>").unwrap(), InputFormat::Svg);
+        assert!(sniff_format(b"<?xml version=\"1.0\"?><html/>").is_err());
+        assert!(sniff_format(b"svg").is_err());
+
+        // Only the signature window counts, as when sniffing a file
+        let mut late = b"<?xml version=\"1.0\"?>".to_vec();
+        late.resize(SIGNATURE_LEN as usize, b' ');
+        late.extend_from_slice(b"<svg/>");
+        assert!(sniff_format(&late).is_err());
+    }
+
+    fn ftyp(brands: &[&[u8; 4]]) -> Vec<u8> {
+        let mut data = ((16 + 4 * brands.len()) as u32).to_be_bytes().to_vec();
+        data.extend_from_slice(b"ftyp");
+        data.extend_from_slice(brands[0]);
+        data.extend_from_slice(&[0; 4]);
+        for brand in brands {
+            data.extend_from_slice(*brand);
+        }
+        data
+    }
+
+    #[test]
+    fn test_sniff_heif_brands() {
+        let iphone = ftyp(&[b"heic", b"mif1", b"heic"]);
+        let avif = ftyp(&[b"avif", b"mif1", b"miaf"]);
+        let generic = ftyp(&[b"mif1", b"avif"]);
+        assert_eq!(sniff_heif(&iphone), Some(InputFormat::Heic));
+        assert_eq!(sniff_heif(&avif), Some(InputFormat::Avif));
+        assert_eq!(sniff_heif(&generic), Some(InputFormat::Avif));
+        assert_eq!(sniff_heif(&ftyp(&[b"isom", b"mp42"])), None);
+
+        // Without the decoder HEIF files are recognized but refused
+        match sniff_format(&iphone) {
+            Ok(format) => assert!(cfg!(feature = "heif") && format == InputFormat::Heic),
+            Err(AppError::UnsupportedFormat(message)) => {
+                assert!(!cfg!(feature = "heif") && message.starts_with("HEIC"))
+            }
+            Err(e) => panic!("unexpected error {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,416 @@
+use crate::append::AppendTarget;
+use crate::embed::compress_flate;
+use crate::error::{AppError, Result};
+use lopdf::encryption::encrypt_object;
+use lopdf::{
+    Dictionary, Document, EncryptionState, Object, ObjectId, ObjectStream, Stream, StringFormat,
+};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Objects packed into one object stream, as lopdf does when saving
+const OBJECTS_PER_STREAM: usize = 100;
+
+/// Where an object can be found in the output
+enum XrefEntry {
+    /// Byte offset of an indirect object
+    Offset(u64),
+    /// Position inside a compressed object stream
+    Compressed { stream: u32, index: u16 },
+}
+
+/// Writes indirect objects to the output as soon as they are finished,
+/// keeping only their locations for the cross-reference section
+///
+/// The builder's `Document` acts as a staging area: [`PdfWriter::write_objects`]
+/// drains it, and [`PdfWriter::finish`] writes what is left together with the
+/// cross-reference section and trailer.
+pub struct PdfWriter {
+    out: CountingWriter,
+    xref: BTreeMap<u32, XrefEntry>,
+    encryption: Option<EncryptionState>,
+    /// Non-stream objects waiting for the next object stream, or `None` when
+    /// they are written as they are
+    packing: Option<Vec<(ObjectId, Object)>>,
+    /// Whether the cross-reference section is a stream rather than a table
+    xref_stream: bool,
+    /// Offset of the previous cross-reference section when writing an
+    /// incremental update
+    prev_xref: Option<u64>,
+    /// Highest object number of the file being updated. Objects up to it
+    /// replace ones in the file and are never packed, since some readers take
+    /// a packed copy from the file's own object streams over the update
+    existing_max_id: u32,
+}
+
+impl PdfWriter {
+    /// Write the file header. Object streams are not used for encrypted output.
+    pub fn new(
+        out: Box<dyn Write + Send>,
+        doc: &Document,
+        encryption: Option<EncryptionState>,
+        object_streams: bool,
+    ) -> Result<Self> {
+        let mut out = CountingWriter {
+            inner: out,
+            position: 0,
+        };
+        writeln!(out, "%PDF-{}", doc.version)?;
+        out.write_all(b"%")?;
+        out.write_all(&doc.binary_mark)?;
+        out.write_all(b"\n")?;
+
+        let packing = (object_streams && encryption.is_none()).then(Vec::new);
+        Ok(PdfWriter {
+            out,
+            xref: BTreeMap::new(),
+            encryption,
+            xref_stream: packing.is_some(),
+            packing,
+            prev_xref: None,
+            existing_max_id: 0,
+        })
+    }
+
+    /// Write an existing file unchanged, so that the objects written next form
+    /// an incremental update to it
+    ///
+    /// The update uses a cross-reference stream only when the file does, and
+    /// only then packs new objects into object streams.
+    pub fn append(
+        out: Box<dyn Write + Send>,
+        target: &AppendTarget,
+        object_streams: bool,
+    ) -> Result<Self> {
+        let original = &target.original;
+        let mut out = CountingWriter {
+            inner: out,
+            position: 0,
+        };
+        out.write_all(original)?;
+        if !original.ends_with(b"\n") && !original.ends_with(b"\r") {
+            out.write_all(b"\n")?;
+        }
+
+        Ok(PdfWriter {
+            out,
+            xref: BTreeMap::new(),
+            encryption: None,
+            packing: (target.xref_stream && object_streams).then(Vec::new),
+            xref_stream: target.xref_stream,
+            prev_xref: Some(target.prev_xref),
+            existing_max_id: target.max_id,
+        })
+    }
+
+    /// Bytes written so far
+    pub fn position(&self) -> u64 {
+        self.out.position
+    }
+
+    /// Write every object staged in `doc` and remove it from memory
+    pub fn write_objects(&mut self, doc: &mut Document) -> Result<()> {
+        for (id, object) in std::mem::take(&mut doc.objects) {
+            match &mut self.packing {
+                Some(pending)
+                    if !matches!(object, Object::Stream(_)) && id.0 > self.existing_max_id =>
+                {
+                    pending.push((id, object));
+                    if pending.len() >= OBJECTS_PER_STREAM {
+                        self.write_object_stream(doc)?;
+                    }
+                }
+                _ => self.write_indirect(id, object)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the remaining objects, the cross-reference section and the
+    /// trailer taken from `doc`, then flush the output
+    pub fn finish(mut self, doc: &mut Document) -> Result<()> {
+        self.write_objects(doc)?;
+        self.write_object_stream(doc)?;
+
+        let mut trailer = doc.trailer.clone();
+        if let Some(state) = &self.encryption {
+            let encrypt = state.encode().map_err(|e| {
+                AppError::PdfGenerationError(format!("Failed to encrypt PDF: {}", e))
+            })?;
+            // The encryption dictionary itself is written in the clear
+            let encrypt_id = doc.new_object_id();
+            self.encryption = None;
+            self.write_indirect(encrypt_id, Object::Dictionary(encrypt))?;
+            trailer.set("Encrypt", encrypt_id);
+        }
+
+        if let Some(prev_xref) = self.prev_xref {
+            trailer.set("Prev", prev_xref as i64);
+        }
+        if self.xref_stream {
+            self.write_xref_stream(doc, trailer)?;
+        } else {
+            self.write_xref_table(doc, trailer)?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn write_indirect(&mut self, id: ObjectId, mut object: Object) -> Result<()> {
+        if let Some(state) = &self.encryption {
+            encrypt_object(state, id, &mut object).map_err(|e| {
+                AppError::PdfGenerationError(format!("Failed to encrypt PDF: {}", e))
+            })?;
+        }
+
+        self.xref.insert(id.0, XrefEntry::Offset(self.out.position));
+        writeln!(self.out, "{} {} obj", id.0, id.1)?;
+        write_object(&mut self.out, &object)?;
+        self.out.write_all(b"\nendobj\n")?;
+        Ok(())
+    }
+
+    /// Pack the waiting non-stream objects into a compressed object stream
+    fn write_object_stream(&mut self, doc: &mut Document) -> Result<()> {
+        let mut pending = match &mut self.packing {
+            Some(pending) if !pending.is_empty() => std::mem::take(pending),
+            _ => return Ok(()),
+        };
+        // Objects are stored in ID order inside the stream
+        pending.sort_by_key(|(id, _)| *id);
+
+        let stream_id = doc.new_object_id();
+        let mut object_stream = ObjectStream::builder()
+            .max_objects(OBJECTS_PER_STREAM)
+            .build();
+        for (index, (id, object)) in pending.into_iter().enumerate() {
+            self.xref.insert(
+                id.0,
+                XrefEntry::Compressed {
+                    stream: stream_id.0,
+                    index: index as u16,
+                },
+            );
+            object_stream.add_object(id, object).map_err(save_error)?;
+        }
+
+        let stream = object_stream.to_stream_object().map_err(save_error)?;
+        self.write_indirect(stream_id, Object::Stream(stream))
+    }
+
+    fn write_xref_table(&mut self, doc: &Document, mut trailer: Dictionary) -> Result<()> {
+        let size = doc.max_id + 1;
+        let start = self.out.position;
+
+        self.out.write_all(b"xref\n")?;
+        for (first, count) in self.subsections(size) {
+            writeln!(self.out, "{} {}", first, count)?;
+            for id in first..first + count {
+                match self.xref.get(&id) {
+                    Some(XrefEntry::Offset(offset)) => {
+                        write!(self.out, "{:010} 00000 n\r\n", offset)?
+                    }
+                    _ => self.out.write_all(b"0000000000 65535 f\r\n")?,
+                }
+            }
+        }
+
+        trailer.set("Size", size as i64);
+        self.out.write_all(b"trailer\n")?;
+        write_object(&mut self.out, &Object::Dictionary(trailer))?;
+        write!(self.out, "\nstartxref\n{}\n%%EOF\n", start)?;
+        Ok(())
+    }
+
+    /// Cross-reference stream, required when objects live in object streams
+    /// and for updates to files that use one
+    fn write_xref_stream(&mut self, doc: &mut Document, mut trailer: Dictionary) -> Result<()> {
+        let xref_id = doc.new_object_id();
+        let start = self.out.position;
+        self.xref.insert(xref_id.0, XrefEntry::Offset(start));
+
+        // Wide enough for the largest offset or object stream number
+        let size = doc.max_id + 1;
+        let largest = start.max(size as u64);
+        let width = (8 - largest.leading_zeros() as usize / 8).max(1);
+
+        let subsections = self.subsections(size);
+        let mut entries = Vec::with_capacity(size as usize * (width + 3));
+        for id in subsections
+            .iter()
+            .flat_map(|&(first, count)| first..first + count)
+        {
+            let (kind, field, extra) = match self.xref.get(&id) {
+                Some(XrefEntry::Offset(offset)) => (1, *offset, 0),
+                Some(XrefEntry::Compressed { stream, index }) => (2, *stream as u64, *index),
+                None => (0, 0, if id == 0 { u16::MAX } else { 0 }),
+            };
+            entries.push(kind);
+            entries.extend_from_slice(&field.to_be_bytes()[8 - width..]);
+            entries.extend_from_slice(&extra.to_be_bytes());
+        }
+        let entries = compress_flate(&entries)?;
+
+        trailer.set("Type", "XRef");
+        trailer.set("Size", size as i64);
+        trailer.set("W", vec![1.into(), (width as i64).into(), 2.into()]);
+        trailer.set("Filter", "FlateDecode");
+        trailer.set("Length", entries.len() as i64);
+        if self.prev_xref.is_some() {
+            let index = subsections
+                .iter()
+                .flat_map(|&(first, count)| [first.into(), count.into()]);
+            trailer.set("Index", index.collect::<Vec<Object>>());
+        }
+
+        writeln!(self.out, "{} {} obj", xref_id.0, xref_id.1)?;
+        write_object(
+            &mut self.out,
+            &Object::Stream(Stream::new(trailer, entries)),
+        )?;
+        write!(self.out, "\nendobj\nstartxref\n{}\n%%EOF\n", start)?;
+        Ok(())
+    }
+
+    /// Runs of object numbers in the cross-reference section, as
+    /// `(first, count)`
+    ///
+    /// A complete file lists every number below `size`; an update lists only
+    /// the objects it wrote, so the file's own entries stay in effect.
+    fn subsections(&self, size: u32) -> Vec<(u32, u32)> {
+        if self.prev_xref.is_none() {
+            return vec![(0, size)];
+        }
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for &id in self.xref.keys() {
+            match runs.last_mut() {
+                Some((first, count)) if *first + *count == id => *count += 1,
+                _ => runs.push((id, 1)),
+            }
+        }
+        runs
+    }
+}
+
+fn save_error(e: lopdf::Error) -> AppError {
+    AppError::PdfGenerationError(format!("Failed to save PDF: {}", e))
+}
+
+/// Output that tracks how many bytes have gone through it, for xref offsets
+struct CountingWriter {
+    inner: Box<dyn Write + Send>,
+    position: u64,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// ============================================================================
+// Object Serialization
+// ============================================================================
+
+/// Serialize a direct object in the same syntax lopdf uses when saving
+fn write_object(out: &mut dyn Write, object: &Object) -> io::Result<()> {
+    match object {
+        Object::Null => out.write_all(b"null"),
+        Object::Boolean(value) => write!(out, "{}", value),
+        Object::Integer(value) => write!(out, "{}", value),
+        Object::Real(value) => write!(out, "{}", value),
+        Object::Name(name) => write_name(out, name),
+        Object::String(text, StringFormat::Literal) => write_literal_string(out, text),
+        Object::String(text, StringFormat::Hexadecimal) => {
+            out.write_all(b"<")?;
+            for byte in text {
+                write!(out, "{:02X}", byte)?;
+            }
+            out.write_all(b">")
+        }
+        Object::Array(items) => {
+            out.write_all(b"[")?;
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.write_all(b" ")?;
+                }
+                write_object(out, item)?;
+            }
+            out.write_all(b"]")
+        }
+        Object::Dictionary(dict) => write_dictionary(out, dict),
+        Object::Stream(stream) => {
+            write_dictionary(out, &stream.dict)?;
+            out.write_all(b"stream\n")?;
+            out.write_all(&stream.content)?;
+            out.write_all(b"\nendstream")
+        }
+        Object::Reference(id) => write!(out, "{} {} R", id.0, id.1),
+    }
+}
+
+fn write_dictionary(out: &mut dyn Write, dict: &Dictionary) -> io::Result<()> {
+    out.write_all(b"<<")?;
+    for (key, value) in dict {
+        write_name(out, key)?;
+        out.write_all(b" ")?;
+        write_object(out, value)?;
+    }
+    out.write_all(b">>")
+}
+
+/// Names escape delimiters, whitespace and non-printable bytes as `#XX`
+fn write_name(out: &mut dyn Write, name: &[u8]) -> io::Result<()> {
+    out.write_all(b"/")?;
+    for &byte in name {
+        if b" \t\n\r\x0C()<>[]{}/%#".contains(&byte) || !(33..=126).contains(&byte) {
+            write!(out, "#{:02X}", byte)?;
+        } else {
+            out.write_all(&[byte])?;
+        }
+    }
+    Ok(())
+}
+
+/// Literal strings escape backslashes, parentheses and carriage returns
+fn write_literal_string(out: &mut dyn Write, text: &[u8]) -> io::Result<()> {
+    out.write_all(b"(")?;
+    for &byte in text {
+        match byte {
+            b'(' | b')' | b'\\' => out.write_all(&[b'\\', byte])?,
+            b'\r' => out.write_all(b"\\r")?,
+            _ => out.write_all(&[byte])?,
+        }
+    }
+    out.write_all(b")")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lopdf::dictionary;
+
+    #[test]
+    fn test_write_object_syntax() {
+        let object = Object::Dictionary(dictionary! {
+            "Type" => "Page",
+            "Name" => Object::Name(b"A B#".to_vec()),
+            "Box" => vec![0.into(), 1.5.into(), Object::Reference((3, 0))],
+            "Text" => Object::string_literal("a(b)\\c\r"),
+            "Id" => Object::String(vec![0xAB, 0x01], StringFormat::Hexadecimal),
+        });
+
+        let mut out = Vec::new();
+        write_object(&mut out, &object).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "<</Type /Page/Name /A#20B#23/Box [0 1.5 3 0 R]/Text (a\\(b\\)\\\\c\\r)/Id <AB01>>>"
+        );
+    }
+}
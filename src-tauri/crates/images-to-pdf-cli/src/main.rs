@@ -0,0 +1,719 @@
+use image_to_pdf_core::utils::pdf::PageRanges;
+use image_to_pdf_core::utils::validation::validate_image_format;
+use image_to_pdf_core::{
+    generate_pdf, Alignment, AppError, AppendTo, Compliance, DocumentMetadata, Encryption,
+    EncryptionAlgorithm, ErrorMode, FillOrder, FitMode, FrameSelection, GenerationProgress,
+    GridLayout, ImageFailure, LengthUnit, Margins, Orientation, PageSize, PdfSettings, Permissions,
+    ProgressStage, Result, TransparencyMode,
+};
+use clap::{Parser, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Convert images to a PDF without starting the desktop application
+#[derive(Debug, Parser)]
+#[command(
+    name = "images-to-pdf-cli",
+    version,
+    after_help = "Exit codes:\n  \
+        0    success\n  \
+        64   invalid arguments or settings\n  \
+        65   unsupported or unreadable image, or one PDF/A cannot express\n  \
+        66   input file not found\n  \
+        70   image processing or PDF generation failed\n  \
+        74   I/O error\n  \
+        130  cancelled"
+)]
+struct Args {
+    /// Image or PDF files, or directories of them, in page order
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Output PDF path
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Page size
+    #[arg(long, value_enum, default_value_t = CliPageSize::A4)]
+    page_size: CliPageSize,
+
+    /// Custom page width in millimeters (with --page-size custom)
+    #[arg(long)]
+    width: Option<f32>,
+
+    /// Custom page height in millimeters (with --page-size custom)
+    #[arg(long)]
+    height: Option<f32>,
+
+    /// Page orientation
+    #[arg(long, value_enum, default_value_t = CliOrientation::Portrait)]
+    orientation: CliOrientation,
+
+    /// How images are fitted on the page
+    #[arg(long, value_enum, default_value_t = CliFitMode::Fit)]
+    fit: CliFitMode,
+
+    /// Page margins as one value for all sides or top,right,bottom,left
+    #[arg(long, value_parser = parse_margins)]
+    margins: Option<[f32; 4]>,
+
+    /// Unit for --margins and --gutter
+    #[arg(long, value_enum, default_value_t = CliLengthUnit::Mm)]
+    margin_unit: CliLengthUnit,
+
+    /// Where images are anchored inside the margins
+    #[arg(long, value_enum, default_value_t = CliAlignment::Center)]
+    align: CliAlignment,
+
+    /// Place several images per page as ROWSxCOLUMNS, e.g. 3x2 (at most 1024 cells)
+    #[arg(long, value_parser = parse_grid)]
+    grid: Option<(u32, u32)>,
+
+    /// Space between grid cells, in --margin-unit
+    #[arg(long, default_value_t = 0.0, requires = "grid")]
+    gutter: f32,
+
+    /// Order in which images fill the grid
+    #[arg(long, value_enum, default_value_t = CliFillOrder::RowMajor, requires = "grid")]
+    fill_order: CliFillOrder,
+
+    /// Frames of multi-page TIFF and animated GIF/WebP files to add: all, first, or a range like 2-5
+    #[arg(long, value_parser = parse_frames, default_value = "all")]
+    frames: FrameSelection,
+
+    /// Pages to copy from a PDF input as FILE=RANGES, e.g. report.pdf=1-3,5,8- (repeatable)
+    #[arg(long = "pages", value_name = "FILE=RANGES", value_parser = parse_pages)]
+    page_ranges: Vec<(String, String)>,
+
+    /// Add the pages to this existing PDF as an incremental update, keeping its
+    /// content and signatures byte for byte (may be the same file as --output)
+    #[arg(long, value_name = "FILE")]
+    append_to: Option<PathBuf>,
+
+    /// Insert the appended pages before this page, counted from 1 (defaults to the end)
+    #[arg(
+        long,
+        value_name = "PAGE",
+        requires = "append_to",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    insert_before: Option<u32>,
+
+    /// Resolution assumed for images that do not declare one
+    #[arg(long, default_value_t = 72.0)]
+    dpi: f32,
+
+    /// Document title
+    #[arg(long)]
+    title: Option<String>,
+
+    /// Document author
+    #[arg(long)]
+    author: Option<String>,
+
+    /// Document subject
+    #[arg(long)]
+    subject: Option<String>,
+
+    /// Comma-separated document keywords
+    #[arg(long)]
+    keywords: Option<String>,
+
+    /// Application that created the original content
+    #[arg(long)]
+    creator: Option<String>,
+
+    /// PDF producer (defaults to this library and its version)
+    #[arg(long)]
+    producer: Option<String>,
+
+    /// Creation date as RFC 3339, e.g. 2024-05-01T12:00:00+02:00 (defaults to now)
+    #[arg(long)]
+    created: Option<String>,
+
+    /// Modification date as RFC 3339 (defaults to the creation date)
+    #[arg(long)]
+    modified: Option<String>,
+
+    /// Password needed to open the PDF (enables encryption)
+    #[arg(long)]
+    user_password: Option<String>,
+
+    /// Password that lifts the permission restrictions (enables encryption)
+    #[arg(long)]
+    owner_password: Option<String>,
+
+    /// Cipher for an encrypted PDF
+    #[arg(long, value_enum, default_value_t = CliEncryption::Aes256)]
+    encryption: CliEncryption,
+
+    /// Forbid printing (enables encryption)
+    #[arg(long)]
+    no_print: bool,
+
+    /// Forbid copying text and images (enables encryption)
+    #[arg(long)]
+    no_copy: bool,
+
+    /// Forbid editing, annotating and assembling pages (enables encryption)
+    #[arg(long)]
+    no_modify: bool,
+
+    /// Archival standard the PDF must conform to
+    #[arg(long, value_enum)]
+    compliance: Option<CliCompliance>,
+
+    /// Embed images without PNG/JPEG optimization
+    #[arg(long)]
+    no_optimize: bool,
+
+    /// Pack objects into compressed object streams (smaller, needs PDF 1.5 readers)
+    #[arg(long)]
+    object_streams: bool,
+
+    /// Flatten transparent images onto the background color
+    #[arg(long)]
+    flatten: bool,
+
+    /// Background color for --flatten as #RRGGBB
+    #[arg(long, requires = "flatten")]
+    background: Option<String>,
+
+    /// What to do with images that cannot be read, decoded or embedded
+    #[arg(long, value_enum, default_value_t = CliErrorMode::Abort)]
+    on_error: CliErrorMode,
+
+    /// Threads preparing images in parallel (defaults to one per CPU core)
+    #[arg(short = 'j', long)]
+    workers: Option<usize>,
+
+    /// Print generation statistics as JSON on stdout instead of the output path
+    #[arg(long)]
+    json: bool,
+
+    /// Do not print progress to stderr
+    #[arg(short, long)]
+    quiet: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliPageSize {
+    A4,
+    A3,
+    A5,
+    Letter,
+    Legal,
+    Custom,
+    /// Size each page to its image's physical dimensions
+    MatchImage,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliOrientation {
+    Portrait,
+    Landscape,
+    /// Follow each image's aspect ratio
+    Auto,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliFitMode {
+    Fit,
+    Fill,
+    Original,
+    /// Fit, but never upscale small images
+    ShrinkOnly,
+    FitWidth,
+    FitHeight,
+    /// Fill the page exactly, ignoring the aspect ratio
+    Stretch,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliLengthUnit {
+    Mm,
+    Pt,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliFillOrder {
+    RowMajor,
+    ColumnMajor,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliCompliance {
+    #[value(name = "pdfa-2b")]
+    PdfA2b,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliEncryption {
+    Aes128,
+    Aes256,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliErrorMode {
+    /// Stop and write no PDF
+    Abort,
+    /// Leave the image out
+    Skip,
+    /// Add a page naming the file and the reason
+    Placeholder,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliAlignment {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// Parse `--frames` as `all`, `first`, `N` or `START-END` (counted from 1)
+fn parse_frames(value: &str) -> std::result::Result<FrameSelection, String> {
+    let number = |part: &str| match part.trim().parse::<u32>() {
+        Ok(0) => Err("frames are counted from 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match value.trim().to_ascii_lowercase().as_str() {
+        "all" => Ok(FrameSelection::All),
+        "first" => Ok(FrameSelection::First),
+        range => {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let (start, end) = (number(start)?, number(end)?);
+            if end < start {
+                return Err("the range end comes before its start".to_string());
+            }
+            Ok(FrameSelection::Range { start, end })
+        }
+    }
+}
+
+/// Parse `--pages` as `FILE=RANGES`
+fn parse_pages(value: &str) -> std::result::Result<(String, String), String> {
+    let (path, ranges) = value
+        .rsplit_once('=')
+        .ok_or_else(|| "expected FILE=RANGES, e.g. report.pdf=1-3,5".to_string())?;
+    PageRanges::parse(ranges).map_err(|e| e.to_string())?;
+
+    Ok((path.to_string(), ranges.to_string()))
+}
+
+/// Parse `--grid` as `ROWSxCOLUMNS`
+fn parse_grid(value: &str) -> std::result::Result<(u32, u32), String> {
+    let (rows, columns) = value
+        .split_once(['x', 'X'])
+        .ok_or_else(|| "expected ROWSxCOLUMNS, e.g. 3x2".to_string())?;
+    let parse = |part: &str| match part.trim().parse::<u32>() {
+        Ok(0) => Err("grid dimensions must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    };
+
+    Ok((parse(rows)?, parse(columns)?))
+}
+
+/// Parse `--margins` as `N` or `TOP,RIGHT,BOTTOM,LEFT`
+fn parse_margins(value: &str) -> std::result::Result<[f32; 4], String> {
+    let values = value
+        .split(',')
+        .map(|part| part.trim().parse::<f32>().map_err(|e| e.to_string()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    match values[..] {
+        [all] => Ok([all; 4]),
+        [top, right, bottom, left] => Ok([top, right, bottom, left]),
+        _ => Err("expected one value or four comma-separated values".to_string()),
+    }
+}
+
+impl Args {
+    fn length_unit(&self) -> LengthUnit {
+        match self.margin_unit {
+            CliLengthUnit::Mm => LengthUnit::Mm,
+            CliLengthUnit::Pt => LengthUnit::Pt,
+        }
+    }
+
+    fn encryption(&self) -> Option<Encryption> {
+        let restricted = self.no_print || self.no_copy || self.no_modify;
+        if self.user_password.is_none() && self.owner_password.is_none() && !restricted {
+            return None;
+        }
+
+        Some(Encryption {
+            user_password: self.user_password.clone().unwrap_or_default(),
+            owner_password: self.owner_password.clone(),
+            algorithm: match self.encryption {
+                CliEncryption::Aes128 => EncryptionAlgorithm::Aes128,
+                CliEncryption::Aes256 => EncryptionAlgorithm::Aes256,
+            },
+            permissions: Permissions {
+                print: !self.no_print,
+                copy: !self.no_copy,
+                modify: !self.no_modify,
+            },
+        })
+    }
+
+    fn settings(&self) -> PdfSettings {
+        PdfSettings {
+            page_size: match self.page_size {
+                CliPageSize::A4 => PageSize::A4,
+                CliPageSize::A3 => PageSize::A3,
+                CliPageSize::A5 => PageSize::A5,
+                CliPageSize::Letter => PageSize::Letter,
+                CliPageSize::Legal => PageSize::Legal,
+                CliPageSize::Custom => PageSize::Custom,
+                CliPageSize::MatchImage => PageSize::MatchImage,
+            },
+            custom_width: self.width,
+            custom_height: self.height,
+            orientation: match self.orientation {
+                CliOrientation::Portrait => Orientation::Portrait,
+                CliOrientation::Landscape => Orientation::Landscape,
+                CliOrientation::Auto => Orientation::Auto,
+            },
+            fit_mode: match self.fit {
+                CliFitMode::Fit => FitMode::Fit,
+                CliFitMode::Fill => FitMode::Fill,
+                CliFitMode::Original => FitMode::Original,
+                CliFitMode::ShrinkOnly => FitMode::ShrinkOnly,
+                CliFitMode::FitWidth => FitMode::FitWidth,
+                CliFitMode::FitHeight => FitMode::FitHeight,
+                CliFitMode::Stretch => FitMode::Stretch,
+            },
+            optimize_images: Some(!self.no_optimize),
+            transparency: Some(if self.flatten {
+                TransparencyMode::Flatten
+            } else {
+                TransparencyMode::Preserve
+            }),
+            background_color: self.background.clone(),
+            fallback_dpi: Some(self.dpi),
+            margins: self.margins.map(|[top, right, bottom, left]| Margins {
+                top,
+                right,
+                bottom,
+                left,
+                unit: self.length_unit(),
+            }),
+            alignment: Some(match self.align {
+                CliAlignment::TopLeft => Alignment::TopLeft,
+                CliAlignment::Top => Alignment::Top,
+                CliAlignment::TopRight => Alignment::TopRight,
+                CliAlignment::Left => Alignment::Left,
+                CliAlignment::Center => Alignment::Center,
+                CliAlignment::Right => Alignment::Right,
+                CliAlignment::BottomLeft => Alignment::BottomLeft,
+                CliAlignment::Bottom => Alignment::Bottom,
+                CliAlignment::BottomRight => Alignment::BottomRight,
+            }),
+            frames: Some(self.frames.clone()),
+            grid: self.grid.map(|(rows, columns)| GridLayout {
+                rows,
+                columns,
+                gutter: self.gutter,
+                unit: self.length_unit(),
+                fill_order: match self.fill_order {
+                    CliFillOrder::RowMajor => FillOrder::RowMajor,
+                    CliFillOrder::ColumnMajor => FillOrder::ColumnMajor,
+                },
+            }),
+            metadata: Some(DocumentMetadata {
+                title: self.title.clone(),
+                author: self.author.clone(),
+                subject: self.subject.clone(),
+                keywords: self.keywords.clone(),
+                creator: self.creator.clone(),
+                producer: self.producer.clone(),
+                creation_date: self.created.clone(),
+                modification_date: self.modified.clone(),
+            }),
+            encryption: self.encryption(),
+            compliance: self.compliance.map(|compliance| match compliance {
+                CliCompliance::PdfA2b => Compliance::PdfA2b,
+            }),
+            object_streams: Some(self.object_streams),
+            workers: self.workers,
+            on_error: Some(match self.on_error {
+                CliErrorMode::Abort => ErrorMode::Abort,
+                CliErrorMode::Skip => ErrorMode::Skip,
+                CliErrorMode::Placeholder => ErrorMode::Placeholder,
+            }),
+            page_ranges: Some(self.page_ranges.iter().cloned().collect()),
+            append_to: self.append_to.as_ref().map(|path| AppendTo {
+                path: path.to_string_lossy().to_string(),
+                index: self.insert_before.map(|page| page as usize - 1),
+            }),
+        }
+    }
+}
+
+/// Expand directories into the supported images and PDFs they contain,
+/// sorted by name
+///
+/// Files are recognized by content, so extensionless scans are included.
+/// Images whose extension names another format are kept and reported when
+/// they are added. `output` is left out, so a directory can be converted
+/// again into a PDF inside it.
+fn collect_image_paths(inputs: &[PathBuf], output: &Path) -> Result<Vec<String>> {
+    let output = output.canonicalize().ok();
+    let mut image_paths = Vec::new();
+
+    for input in inputs {
+        if !input.is_dir() {
+            image_paths.push(input.to_string_lossy().to_string());
+            continue;
+        }
+
+        let mut entries = std::fs::read_dir(input)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        entries.sort();
+
+        image_paths.extend(
+            entries
+                .into_iter()
+                .filter(|path| path.is_file() && path.canonicalize().ok() != output)
+                .map(|path| path.to_string_lossy().to_string())
+                .filter(|path| {
+                    !matches!(
+                        validate_image_format(path),
+                        Err(AppError::UnsupportedFormat(_))
+                    )
+                }),
+        );
+    }
+
+    Ok(image_paths)
+}
+
+/// Warn about an image that was skipped or replaced by a placeholder, as soon
+/// as it happens, so the warning is not lost if the job fails later
+fn print_failure(failure: &ImageFailure) {
+    match failure.frame {
+        Some(frame) => eprintln!("warning: {} (frame {}): {}", failure.path, frame, failure.message),
+        None => eprintln!("warning: {}: {}", failure.path, failure.message),
+    }
+}
+
+fn print_progress(progress: GenerationProgress) {
+    match progress.stage {
+        ProgressStage::Processing => {
+            eprintln!("[{}/{}] {}", progress.index + 1, progress.total, progress.path)
+        }
+        ProgressStage::Saving => eprintln!("Saving PDF..."),
+        ProgressStage::Finished => eprintln!("Done ({} bytes)", progress.bytes_written),
+        ProgressStage::Embedded | ProgressStage::Failed => {}
+    }
+}
+
+/// Cancel the job on Ctrl-C, so it stops between images, removes its
+/// partial output and exits with 130; a second Ctrl-C exits at once
+fn cancel_on_interrupt(cancelled: &Arc<AtomicBool>) {
+    let flag = cancelled.clone();
+    // SAFETY: the handler only touches an atomic and calls `_exit`, both of
+    // which are async-signal-safe
+    let registered = unsafe {
+        signal_hook_registry::register(libc::SIGINT, move || {
+            if flag.swap(true, Ordering::Relaxed) {
+                libc::_exit(130);
+            }
+        })
+    };
+    if let Err(e) = registered {
+        eprintln!("warning: Ctrl-C will not remove partial output: {}", e);
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    cancel_on_interrupt(&cancelled);
+
+    let result = collect_image_paths(&args.inputs, &args.output).and_then(|image_paths| {
+        let on_progress = |progress: GenerationProgress| {
+            if let Some(failure) = &progress.failure {
+                print_failure(failure);
+            }
+            if !args.quiet {
+                print_progress(progress);
+            }
+        };
+
+        generate_pdf(
+            image_paths,
+            args.output.to_string_lossy().to_string(),
+            args.settings(),
+            &on_progress,
+            &cancelled,
+        )
+    });
+
+    match result {
+        Ok(stats) => {
+            if !args.quiet && stats.duplicates_collapsed > 0 {
+                eprintln!(
+                    "Embedded {} repeated images once ({} bytes saved)",
+                    stats.duplicates_collapsed, stats.duplicate_bytes_saved
+                );
+            }
+            if args.json {
+                match serde_json::to_string_pretty(&stats) {
+                    Ok(json) => println!("{}", json),
+                    Err(e) => {
+                        eprintln!("error: {}", e);
+                        return ExitCode::from(70);
+                    }
+                }
+            } else {
+                println!("{}", args.output.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_interrupt_sets_cancelled() {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        cancel_on_interrupt(&cancelled);
+
+        assert_eq!(unsafe { libc::raise(libc::SIGINT) }, 0);
+        assert!(cancelled.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_collect_image_paths_filters_directory() {
+        let dir = std::env::temp_dir().join("test_cli_collect_images");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("b.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        std::fs::write(dir.join("a.jpg"), b"\xff\xd8\xff\xe0").unwrap();
+        std::fs::write(dir.join("c_scan"), b"II*\x00").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"").unwrap();
+        std::fs::write(dir.join("d.pdf"), b"%PDF-1.7\n").unwrap();
+        std::fs::write(dir.join("out.pdf"), b"%PDF-1.7\n").unwrap();
+
+        let paths = collect_image_paths(std::slice::from_ref(&dir), &dir.join("out.pdf")).unwrap();
+        let names: Vec<_> = paths
+            .iter()
+            .map(|p| PathBuf::from(p).file_name().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["a.jpg", "b.png", "c_scan", "d.pdf"]);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_encryption_only_when_requested() {
+        let args = Args::parse_from(["images-to-pdf-cli", "a.png", "-o", "out.pdf"]);
+        assert_eq!(args.encryption(), None);
+
+        let args = Args::parse_from([
+            "images-to-pdf-cli",
+            "a.png",
+            "-o",
+            "out.pdf",
+            "--no-copy",
+            "--encryption",
+            "aes128",
+        ]);
+        let encryption = args.encryption().unwrap();
+        assert_eq!(encryption.user_password, "");
+        assert_eq!(encryption.algorithm, EncryptionAlgorithm::Aes128);
+        assert!(encryption.permissions.print && !encryption.permissions.copy);
+    }
+
+    #[test]
+    fn test_parse_margins() {
+        assert_eq!(parse_margins("10"), Ok([10.0; 4]));
+        assert_eq!(parse_margins("1, 2,3,4"), Ok([1.0, 2.0, 3.0, 4.0]));
+        assert!(parse_margins("1,2").is_err());
+        assert!(parse_margins("wide").is_err());
+    }
+
+    #[test]
+    fn test_parse_grid() {
+        assert_eq!(parse_grid("3x2"), Ok((3, 2)));
+        assert_eq!(parse_grid("1X4"), Ok((1, 4)));
+        assert!(parse_grid("0x2").is_err());
+        assert!(parse_grid("3").is_err());
+    }
+
+    #[test]
+    fn test_parse_pages() {
+        assert_eq!(
+            parse_pages("scans/a=b.pdf=1-3,5"),
+            Ok(("scans/a=b.pdf".to_string(), "1-3,5".to_string()))
+        );
+        assert!(parse_pages("report.pdf").is_err());
+        assert!(parse_pages("report.pdf=3-1").is_err());
+    }
+
+    #[test]
+    fn test_append_to_settings() {
+        let args = Args::parse_from(["images-to-pdf-cli", "a.png", "-o", "out.pdf"]);
+        assert_eq!(args.settings().append_to, None);
+
+        let args = Args::parse_from([
+            "images-to-pdf-cli",
+            "a.png",
+            "-o",
+            "archive.pdf",
+            "--append-to",
+            "archive.pdf",
+            "--insert-before",
+            "3",
+        ]);
+        assert_eq!(
+            args.settings().append_to,
+            Some(AppendTo {
+                path: "archive.pdf".to_string(),
+                index: Some(2),
+            })
+        );
+
+        let args = ["images-to-pdf-cli", "a.png", "-o", "out.pdf", "--insert-before", "1"];
+        assert!(Args::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn test_parse_frames() {
+        assert_eq!(parse_frames("all"), Ok(FrameSelection::All));
+        assert_eq!(parse_frames("First"), Ok(FrameSelection::First));
+        assert_eq!(parse_frames("3"), Ok(FrameSelection::Range { start: 3, end: 3 }));
+        assert_eq!(parse_frames("2-5"), Ok(FrameSelection::Range { start: 2, end: 5 }));
+        assert!(parse_frames("5-2").is_err());
+        assert!(parse_frames("0").is_err());
+    }
+
+    #[test]
+    fn test_missing_input_exit_code() {
+        let err: AppError =
+            collect_image_paths(&[PathBuf::from("/nonexistent/dir/")], Path::new("out.pdf"))
+                .and_then(|paths| image_to_pdf_core::utils::validation::validate_image(&paths[0]))
+                .unwrap_err();
+        assert_eq!(err.exit_code(), 66);
+    }
+}
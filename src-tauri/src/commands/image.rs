@@ -1,17 +1,7 @@
-use crate::error::{AppError, Result};
-use crate::utils::validation::validate_image;
+use image_to_pdf_core::utils::validation::validate_image;
+use image_to_pdf_core::{ImageInfo, Result};
 use serde::{Deserialize, Serialize};
 
-/// Image metadata
-#[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ImageInfo {
-    pub width: u32,
-    pub height: u32,
-    pub format: String,
-    pub size_bytes: u64,
-}
-
 /// Validation result
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidationResult {
@@ -47,52 +37,13 @@ pub fn validate_images(paths: Vec<String>) -> ValidationResult {
 /// Get metadata for a single image
 #[tauri::command]
 pub fn get_image_info(path: String) -> Result<ImageInfo> {
-    // Validate first
-    validate_image(&path)?;
-
-    // Open and read image
-    let img = image::open(&path)?;
-
-    // Get file size
-    let metadata = std::fs::metadata(&path)?;
-    let size_bytes = metadata.len();
-
-    // Extract format from path
-    let format = std::path::Path::new(&path)
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("unknown")
-        .to_uppercase();
-
-    Ok(ImageInfo {
-        width: img.width(),
-        height: img.height(),
-        format,
-        size_bytes,
-    })
+    image_to_pdf_core::image_info(&path)
 }
 
 /// Get image thumbnail as base64 data URL
 #[tauri::command]
 pub fn get_image_thumbnail(path: String, size: u32) -> Result<String> {
-    // Validate first
-    validate_image(&path)?;
-
-    // Open image
-    let img = image::open(&path)
-        .map_err(|e| AppError::ImageReadError(format!("Failed to open image: {}", e)))?;
-
-    // Create thumbnail (maintaining aspect ratio)
-    let thumbnail = img.thumbnail(size, size);
-
-    // Convert to PNG bytes
-    let mut png_bytes: Vec<u8> = Vec::new();
-    thumbnail
-        .write_to(
-            &mut std::io::Cursor::new(&mut png_bytes),
-            image::ImageFormat::Png,
-        )
-        .map_err(|e| AppError::ImageProcessingError(format!("Failed to encode thumbnail: {}", e)))?;
+    let png_bytes = image_to_pdf_core::image_thumbnail(&path, size)?;
 
     // Convert to base64
     let base64_string = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &png_bytes);
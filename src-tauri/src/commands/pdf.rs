@@ -1,192 +1,83 @@
-use crate::error::{AppError, Result};
-use crate::utils::validation::validate_image;
-use crate::utils::optimize::optimize_image;
-use lopdf::{content::Content, dictionary, Document, Object, Stream};
+use image_to_pdf_core::{AppError, GenerationProgress, GenerationStats, ImageFailure, PdfSettings};
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Manager};
 
 // ============================================================================
 // Types (must match TypeScript types exactly)
 // ============================================================================
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum PageSize {
-    A4,
-    A3,
-    A5,
-    Letter,
-    Legal,
-    Custom,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum Orientation {
-    Portrait,
-    Landscape,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub enum FitMode {
-    Fit,
-    Fill,
-    Original,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct PdfSettings {
-    pub page_size: PageSize,
-    pub custom_width: Option<f32>,
-    pub custom_height: Option<f32>,
-    pub orientation: Orientation,
-    pub fit_mode: FitMode,
-    pub optimize_images: Option<bool>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
 pub struct GenerationResult {
     pub success: bool,
     pub output_path: Option<String>,
     pub error: Option<String>,
+    /// Images skipped or replaced by a placeholder, also when the job failed
+    /// afterwards
+    pub failures: Vec<ImageFailure>,
+    /// Page count, sizes, timing and skipped images of a successful job
+    pub stats: Option<GenerationStats>,
 }
 
-// ============================================================================
-// Page Dimensions (in points: 1 point = 1/72 inch)
-// ============================================================================
-
-/// Get page dimensions in points based on page size and orientation
-fn get_page_dimensions(settings: &PdfSettings) -> Result<(f32, f32)> {
-    // Standard sizes in points
-    let (mut width, mut height) = match settings.page_size {
-        PageSize::A4 => (595.0, 842.0),        // 210mm x 297mm
-        PageSize::A3 => (842.0, 1191.0),       // 297mm x 420mm
-        PageSize::A5 => (420.0, 595.0),        // 148mm x 210mm
-        PageSize::Letter => (612.0, 792.0),    // 8.5" x 11"
-        PageSize::Legal => (612.0, 1008.0),    // 8.5" x 14"
-        PageSize::Custom => {
-            let w = settings
-                .custom_width
-                .ok_or(AppError::InvalidDimensions)?;
-            let h = settings
-                .custom_height
-                .ok_or(AppError::InvalidDimensions)?;
-
-            if w <= 0.0 || h <= 0.0 {
-                return Err(AppError::InvalidDimensions);
-            }
-
-            // Convert mm to points (1 mm = 2.83465 points)
-            (w * 2.83465, h * 2.83465)
+impl GenerationResult {
+    fn failed(error: AppError) -> Self {
+        GenerationResult {
+            success: false,
+            output_path: None,
+            error: Some(error.to_string()),
+            failures: Vec::new(),
+            stats: None,
         }
-    };
-
-    // Swap dimensions for landscape
-    if matches!(settings.orientation, Orientation::Landscape) {
-        std::mem::swap(&mut width, &mut height);
     }
-
-    Ok((width, height))
 }
 
-// ============================================================================
-// Image Placement Calculation
-// ============================================================================
-
-struct ImagePlacement {
-    x: f32,
-    y: f32,
-    width: f32,
-    height: f32,
+/// Progress event payload tagged with the job it belongs to
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEvent {
+    job_id: String,
+    #[serde(flatten)]
+    progress: GenerationProgress,
 }
 
-/// Calculate image position and dimensions based on fit mode
-fn calculate_image_placement(
-    img_width: u32,
-    img_height: u32,
-    page_width: f32,
-    page_height: f32,
-    fit_mode: &FitMode,
-) -> ImagePlacement {
-    let img_width_f = img_width as f32;
-    let img_height_f = img_height as f32;
-
-    match fit_mode {
-        FitMode::Fit => {
-            // Fit image to page preserving aspect ratio
-            let scale_w = page_width / img_width_f;
-            let scale_h = page_height / img_height_f;
-            let scale = scale_w.min(scale_h);
-
-            let width = img_width_f * scale;
-            let height = img_height_f * scale;
-
-            // Center the image
-            let x = (page_width - width) / 2.0;
-            let y = (page_height - height) / 2.0;
-
-            ImagePlacement { x, y, width, height }
+/// Event name used for generation progress
+pub const PROGRESS_EVENT: &str = "generation-progress";
+
+/// Cancellation flags of running generation jobs, keyed by job id
+#[derive(Default)]
+pub struct GenerationJobs(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+impl GenerationJobs {
+    /// Refuses an id that is still running, whose flag would otherwise be
+    /// replaced and then removed when the first job finishes
+    fn register(&self, job_id: &str) -> Result<Arc<AtomicBool>, AppError> {
+        let mut jobs = self.0.lock().unwrap();
+        if jobs.contains_key(job_id) {
+            return Err(AppError::InvalidSettings(format!(
+                "A job with id {} is already running",
+                job_id
+            )));
         }
 
-        FitMode::Fill => {
-            // Fill page, may crop image
-            let scale_w = page_width / img_width_f;
-            let scale_h = page_height / img_height_f;
-            let scale = scale_w.max(scale_h);
-
-            let width = img_width_f * scale;
-            let height = img_height_f * scale;
-
-            // Center the image
-            let x = (page_width - width) / 2.0;
-            let y = (page_height - height) / 2.0;
-
-            ImagePlacement { x, y, width, height }
-        }
-
-        FitMode::Original => {
-            // Use original size, centered
-            let x = (page_width - img_width_f) / 2.0;
-            let y = (page_height - img_height_f) / 2.0;
-
-            ImagePlacement {
-                x,
-                y,
-                width: img_width_f,
-                height: img_height_f,
-            }
-        }
+        let flag = Arc::new(AtomicBool::new(false));
+        jobs.insert(job_id.to_string(), flag.clone());
+        Ok(flag)
     }
-}
-
-// ============================================================================
-// Image Optimization Helper
-// ============================================================================
-
-/// Create optimized version of an image in a temporary file
-///
-/// Returns Some(temp_path) if optimization was successful, None if not needed
-fn create_optimized_image(input_path: &Path) -> Result<Option<PathBuf>> {
-    // Check if the image format supports optimization
-    let format = image::ImageFormat::from_path(input_path).ok();
 
-    match format {
-        Some(image::ImageFormat::Png) | Some(image::ImageFormat::Jpeg) => {
-            // Create temporary file
-            let temp_dir = std::env::temp_dir();
-            let file_name = input_path
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("temp");
-            let temp_path = temp_dir.join(format!("optimized_{}", file_name));
-
-            // Optimize image (quality 85 for JPEG)
-            optimize_image(input_path, &temp_path, 85)?;
+    fn finish(&self, job_id: &str) {
+        self.0.lock().unwrap().remove(job_id);
+    }
 
-            Ok(Some(temp_path))
-        }
-        _ => {
-            // For other formats, don't optimize
-            Ok(None)
+    fn cancel(&self, job_id: &str) -> bool {
+        match self.0.lock().unwrap().get(job_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
         }
     }
 }
@@ -196,260 +87,72 @@ fn create_optimized_image(input_path: &Path) -> Result<Option<PathBuf>> {
 // ============================================================================
 
 /// Generate PDF from images
+///
+/// Runs on a blocking worker and emits `PROGRESS_EVENT` for every image.
+/// The job can be aborted with `cancel_generation` using the same `job_id`.
 #[tauri::command]
-pub fn generate_pdf(
+pub async fn generate_pdf(
+    app: AppHandle,
+    job_id: String,
     image_paths: Vec<String>,
     output_path: String,
     settings: PdfSettings,
 ) -> GenerationResult {
-    match generate_pdf_internal(image_paths, output_path.clone(), settings) {
-        Ok(_) => GenerationResult {
+    let cancelled = match app.state::<GenerationJobs>().register(&job_id) {
+        Ok(cancelled) => cancelled,
+        Err(e) => return GenerationResult::failed(e),
+    };
+
+    let worker_app = app.clone();
+    let worker_job_id = job_id.clone();
+    let worker_output = output_path.clone();
+    let (result, failures) = tauri::async_runtime::spawn_blocking(move || {
+        // Collected from progress, which still has them when the job fails
+        let failures = Mutex::new(Vec::new());
+        let on_progress = |progress: GenerationProgress| {
+            if let Some(failure) = &progress.failure {
+                failures.lock().unwrap().push(failure.clone());
+            }
+            let event = ProgressEvent {
+                job_id: worker_job_id.clone(),
+                progress,
+            };
+            worker_app.emit(PROGRESS_EVENT, event).ok(); // Progress is best effort
+        };
+
+        let result =
+            image_to_pdf_core::generate_pdf(image_paths, worker_output, settings, &on_progress, &cancelled);
+        (result, failures.into_inner().unwrap())
+    })
+    .await
+    .unwrap_or_else(|e| {
+        let error = AppError::PdfGenerationError(format!("Worker failed: {}", e));
+        (Err(error), Vec::new())
+    });
+
+    app.state::<GenerationJobs>().finish(&job_id);
+
+    match result {
+        Ok(stats) => GenerationResult {
             success: true,
             output_path: Some(output_path),
             error: None,
+            failures,
+            stats: Some(stats),
         },
         Err(e) => GenerationResult {
-            success: false,
-            output_path: None,
-            error: Some(e.to_string()),
+            failures,
+            ..GenerationResult::failed(e)
         },
     }
 }
 
-fn generate_pdf_internal(
-    image_paths: Vec<String>,
-    output_path: String,
-    settings: PdfSettings,
-) -> Result<()> {
-    if image_paths.is_empty() {
-        return Err(AppError::NoImages);
-    }
-
-    // Get page dimensions
-    let (page_width, page_height) = get_page_dimensions(&settings)?;
-
-    // Create new PDF document
-    let mut doc = Document::with_version("1.5");
-
-    let pages_id = doc.new_object_id();
-    let catalog_id = doc.add_object(dictionary! {
-        "Type" => "Catalog",
-        "Pages" => pages_id,
-    });
-    doc.trailer.set("Root", catalog_id);
-    let mut page_ids = Vec::new();
-
-    // Process each image and create pages
-    for image_path in &image_paths {
-        let page_id = add_image_page(
-            &mut doc,
-            image_path,
-            page_width,
-            page_height,
-            &settings.fit_mode,
-            settings.optimize_images.unwrap_or(true),
-        )?;
-        page_ids.push(page_id);
-    }
-
-    // Create pages dictionary
-    let pages_dict = dictionary! {
-        "Type" => "Pages",
-        "Count" => page_ids.len() as u32,
-        "Kids" => page_ids.iter().map(|&id| Object::Reference(id)).collect::<Vec<_>>(),
-    };
-
-    doc.objects.insert(pages_id, Object::Dictionary(pages_dict));
-
-    // Save PDF
-    doc.save(&output_path)
-        .map_err(|e| AppError::PdfGenerationError(format!("Failed to save PDF: {}", e)))?;
-
-    Ok(())
-}
-
-/// Add a single image as a page to the PDF
-fn add_image_page(
-    doc: &mut Document,
-    image_path: &str,
-    page_width: f32,
-    page_height: f32,
-    fit_mode: &FitMode,
-    optimize: bool,
-) -> Result<(u32, u16)> {
-    // Validate image
-    validate_image(image_path)?;
-
-    // Optimize image before adding to PDF (if enabled)
-    let input_path = Path::new(image_path);
-    let optimized_path = if optimize {
-        create_optimized_image(input_path)?
-    } else {
-        None
-    };
-
-    // Use optimized image for PDF
-    let image_to_use = optimized_path.as_deref().unwrap_or(input_path);
-
-    // Load image metadata
-    let img = image::open(image_to_use)?;
-    let img_width = img.width();
-    let img_height = img.height();
-
-    // Calculate placement
-    let placement = calculate_image_placement(
-        img_width,
-        img_height,
-        page_width,
-        page_height,
-        fit_mode,
-    );
-
-    // Detect image format and create compressed image stream
-    let format = image::ImageFormat::from_path(image_to_use)
-        .map_err(|e| AppError::UnsupportedFormat(format!("Cannot detect format: {}", e)))?;
-
-    let image_id = match format {
-        image::ImageFormat::Jpeg => {
-            // Use original JPEG data with DCTDecode filter
-            let jpeg_data = std::fs::read(image_to_use)?;
-
-            doc.add_object(Stream::new(
-                dictionary! {
-                    "Type" => "XObject",
-                    "Subtype" => "Image",
-                    "Width" => img_width,
-                    "Height" => img_height,
-                    "ColorSpace" => "DeviceRGB",
-                    "BitsPerComponent" => 8,
-                    "Filter" => "DCTDecode",
-                    "Length" => jpeg_data.len() as i64,
-                },
-                jpeg_data,
-            ))
-        }
-        image::ImageFormat::Png => {
-            // Always convert to RGB (handle transparency with white background)
-            let img_rgb = img.to_rgb8();
-            let raw_data = img_rgb.into_raw();
-
-            // Compress with flate
-            use std::io::Write;
-            let mut encoder = flate2::write::ZlibEncoder::new(
-                Vec::new(),
-                flate2::Compression::best()
-            );
-            encoder.write_all(&raw_data)
-                .map_err(|e| AppError::ImageProcessingError(format!("Compression failed: {}", e)))?;
-            let compressed_data = encoder.finish()
-                .map_err(|e| AppError::ImageProcessingError(format!("Compression failed: {}", e)))?;
-
-            doc.add_object(Stream::new(
-                dictionary! {
-                    "Type" => "XObject",
-                    "Subtype" => "Image",
-                    "Width" => img_width,
-                    "Height" => img_height,
-                    "ColorSpace" => "DeviceRGB",
-                    "BitsPerComponent" => 8,
-                    "Filter" => "FlateDecode",
-                    "Length" => compressed_data.len() as i64,
-                },
-                compressed_data,
-            ))
-        }
-        _ => {
-            // For other formats, convert to RGB and compress
-            let img_rgb = img.to_rgb8();
-            let raw_data = img_rgb.into_raw();
-
-            use std::io::Write;
-            let mut encoder = flate2::write::ZlibEncoder::new(
-                Vec::new(),
-                flate2::Compression::best()
-            );
-            encoder.write_all(&raw_data)
-                .map_err(|e| AppError::ImageProcessingError(format!("Compression failed: {}", e)))?;
-            let compressed_data = encoder.finish()
-                .map_err(|e| AppError::ImageProcessingError(format!("Compression failed: {}", e)))?;
-
-            doc.add_object(Stream::new(
-                dictionary! {
-                    "Type" => "XObject",
-                    "Subtype" => "Image",
-                    "Width" => img_width,
-                    "Height" => img_height,
-                    "ColorSpace" => "DeviceRGB",
-                    "BitsPerComponent" => 8,
-                    "Filter" => "FlateDecode",
-                    "Length" => compressed_data.len() as i64,
-                },
-                compressed_data,
-            ))
-        }
-    };
-
-    // Cleanup temporary file if it was created
-    if let Some(temp_path) = optimized_path {
-        std::fs::remove_file(temp_path).ok(); // Ignore cleanup errors
-    }
-
-    // Create content stream to place the image
-    let content = Content {
-        operations: vec![
-            // Save graphics state
-            lopdf::content::Operation::new("q", vec![]),
-            // Transform matrix: [a b c d e f]
-            // a = width scale, d = height scale, e = x position, f = y position
-            lopdf::content::Operation::new(
-                "cm",
-                vec![
-                    placement.width.into(),
-                    0.into(),
-                    0.into(),
-                    placement.height.into(),
-                    placement.x.into(),
-                    placement.y.into(),
-                ],
-            ),
-            // Draw image
-            lopdf::content::Operation::new("Do", vec!["Im1".into()]),
-            // Restore graphics state
-            lopdf::content::Operation::new("Q", vec![]),
-        ],
-    };
-
-    let content_data = content.encode()
-        .map_err(|e| AppError::PdfGenerationError(format!("Failed to encode content: {}", e)))?;
-
-    let content_id = doc.add_object(Stream::new(
-        dictionary! {
-            "Length" => content_data.len() as i64,
-        },
-        content_data,
-    ));
-
-    // Get pages reference from catalog
-    let catalog = doc.catalog()
-        .map_err(|e| AppError::PdfGenerationError(format!("Failed to get catalog: {}", e)))?;
-    let pages_ref = catalog.get(b"Pages")
-        .map_err(|_| AppError::PdfGenerationError("Failed to get Pages from catalog".to_string()))?
-        .clone();
-
-    // Create page
-    let page_id = doc.add_object(dictionary! {
-        "Type" => "Page",
-        "Parent" => pages_ref,
-        "MediaBox" => vec![0.into(), 0.into(), page_width.into(), page_height.into()],
-        "Contents" => content_id,
-        "Resources" => dictionary! {
-            "XObject" => dictionary! {
-                "Im1" => image_id,
-            },
-        },
-    });
-
-    Ok(page_id)
+/// Request cancellation of a running generation job
+///
+/// Returns false if no job with this id is running.
+#[tauri::command]
+pub fn cancel_generation(app: AppHandle, job_id: String) -> bool {
+    app.state::<GenerationJobs>().cancel(&job_id)
 }
 
 #[cfg(test)]
@@ -457,50 +160,30 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_get_page_dimensions_a4_portrait() {
-        let settings = PdfSettings {
-            page_size: PageSize::A4,
-            custom_width: None,
-            custom_height: None,
-            orientation: Orientation::Portrait,
-            fit_mode: FitMode::Fit,
-            optimize_images: Some(true),
-        };
+    fn test_cancel_registered_job() {
+        let jobs = GenerationJobs::default();
+        let flag = jobs.register("job-1").unwrap();
 
-        let (w, h) = get_page_dimensions(&settings).unwrap();
-        assert_eq!(w, 595.0);
-        assert_eq!(h, 842.0);
-    }
-
-    #[test]
-    fn test_get_page_dimensions_a4_landscape() {
-        let settings = PdfSettings {
-            page_size: PageSize::A4,
-            custom_width: None,
-            custom_height: None,
-            orientation: Orientation::Landscape,
-            fit_mode: FitMode::Fit,
-            optimize_images: Some(true),
-        };
+        assert!(jobs.cancel("job-1"));
+        assert!(flag.load(Ordering::Relaxed));
 
-        let (w, h) = get_page_dimensions(&settings).unwrap();
-        assert_eq!(w, 842.0);
-        assert_eq!(h, 595.0);
+        jobs.finish("job-1");
+        assert!(!jobs.cancel("job-1"));
     }
 
     #[test]
-    fn test_get_page_dimensions_custom() {
-        let settings = PdfSettings {
-            page_size: PageSize::Custom,
-            custom_width: Some(100.0),
-            custom_height: Some(200.0),
-            orientation: Orientation::Portrait,
-            fit_mode: FitMode::Fit,
-            optimize_images: Some(true),
-        };
-
-        let (w, h) = get_page_dimensions(&settings).unwrap();
-        assert!((w - 283.465).abs() < 0.01);
-        assert!((h - 566.93).abs() < 0.01);
+    fn test_register_rejects_running_job_id() {
+        let jobs = GenerationJobs::default();
+        let flag = jobs.register("job-1").unwrap();
+
+        assert!(matches!(
+            jobs.register("job-1"),
+            Err(AppError::InvalidSettings(_))
+        ));
+        assert!(jobs.cancel("job-1"));
+        assert!(flag.load(Ordering::Relaxed));
+
+        jobs.finish("job-1");
+        assert!(jobs.register("job-1").is_ok());
     }
 }
@@ -1,11 +1,9 @@
 // Modules
 mod commands;
-mod error;
-mod utils;
 
 // Re-export for convenience
 use commands::image::{get_image_info, get_image_thumbnail, validate_images};
-use commands::pdf::generate_pdf;
+use commands::pdf::{cancel_generation, generate_pdf, GenerationJobs};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -13,11 +11,13 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(GenerationJobs::default())
         .invoke_handler(tauri::generate_handler![
             validate_images,
             get_image_info,
             get_image_thumbnail,
-            generate_pdf
+            generate_pdf,
+            cancel_generation
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,2 +0,0 @@
-pub mod validation;
-pub mod optimize;
@@ -1,14 +1,51 @@
 import { FileDown } from 'lucide-react';
 import { Button } from './ui/button';
 import { useCanGenerate, useImages, useSettings, useAppStore } from '@/store/useAppStore';
-import { selectOutputPath, generatePdf } from '@/lib/tauri';
+import {
+  selectOutputPath,
+  generatePdf,
+  onGenerationProgress,
+} from '@/lib/tauri';
 import { toast } from 'sonner';
+import { extractFileName, formatFileSize } from '@/types';
+import type { GenerationStats, ImageFailure } from '@/types';
+
+/** One-line summary of a finished job for the success toast */
+function describeStats(stats: GenerationStats): string {
+  const parts = [
+    `страниц: ${stats.pageCount}`,
+    formatFileSize(stats.fileSize),
+    `${(stats.elapsedMs / 1000).toFixed(1)} с`,
+  ];
+  if (stats.bytesSaved > 0) {
+    parts.push(`сэкономлено ${formatFileSize(stats.bytesSaved)}`);
+  }
+  if (stats.duplicatesCollapsed > 0) {
+    parts.push(
+      `повторов встроено один раз: ${stats.duplicatesCollapsed} (${formatFileSize(stats.duplicateBytesSaved)})`
+    );
+  }
+  return parts.join(', ');
+}
+
+/** Warn about images that were skipped or replaced by a placeholder */
+function reportFailures(failures: ImageFailure[]) {
+  if (failures.length === 0) return;
+  toast.warning(`Не удалось добавить изображений: ${failures.length}`, {
+    description: failures
+      .map((failure) => {
+        const frame = failure.frame ? ` (кадр ${failure.frame})` : '';
+        return `${extractFileName(failure.path)}${frame}: ${failure.message}`;
+      })
+      .join('\n'),
+  });
+}
 
 export function ExportButton() {
   const canGenerate = useCanGenerate();
   const images = useImages();
   const settings = useSettings();
-  const { setIsGenerating, setProgress } = useAppStore();
+  const { setIsGenerating, setProgress, setCurrentJobId } = useAppStore();
 
   const handleExport = async () => {
     // Check if we have images
@@ -26,8 +63,8 @@ export function ExportButton() {
     }
 
     try {
-      // Open save dialog
-      const outputPath = await selectOutputPath('images.pdf');
+      // Open save dialog; an appended PDF is offered to be updated in place
+      const outputPath = await selectOutputPath(settings.appendTo?.path ?? 'images.pdf');
 
       if (!outputPath) {
         // User cancelled
@@ -35,35 +72,49 @@ export function ExportButton() {
       }
 
       // Start generating
+      const jobId = crypto.randomUUID();
+      setCurrentJobId(jobId);
       setIsGenerating(true);
       setProgress(0);
 
-      // Simulate progress (in real app, this would be done via channels from Rust)
-      let currentProgress = 0;
-      const progressInterval = setInterval(() => {
-        currentProgress += 10;
-        if (currentProgress >= 90) {
-          clearInterval(progressInterval);
-          setProgress(90);
-        } else {
-          setProgress(currentProgress);
-        }
-      }, 200);
+      // Track progress reported by the backend for this job
+      const unlisten = await onGenerationProgress((event) => {
+        if (event.jobId !== jobId) return;
+        const done = event.stage === 'Processing' ? event.index : event.index + 1;
+        setProgress(Math.round((done / event.total) * 95));
+      });
 
       // Get image paths
       const imagePaths = images.map((img) => img.path);
 
+      // Page ranges entered for PDF inputs
+      const pageRanges = Object.fromEntries(
+        images
+          .filter((img) => img.pageRange?.trim())
+          .map((img) => [img.path, img.pageRange!.trim()])
+      );
+
       // Generate PDF
-      const generationResult = await generatePdf(imagePaths, outputPath, settings);
+      const generationResult = await generatePdf(
+        jobId,
+        imagePaths,
+        outputPath,
+        { ...settings, pageRanges }
+      ).finally(() => {
+        unlisten();
+        setCurrentJobId(null);
+      });
 
-      clearInterval(progressInterval);
       setProgress(100);
 
       if (generationResult.success) {
+        const stats = generationResult.stats;
         toast.success('PDF успешно создан', {
-          description: outputPath,
+          description: stats ? `${outputPath} (${describeStats(stats)})` : outputPath,
         });
 
+        reportFailures(generationResult.failures);
+
         // Keep progress dialog visible for a moment to show 100%
         setTimeout(() => {
           setIsGenerating(false);
@@ -72,6 +123,7 @@ export function ExportButton() {
         toast.error('Ошибка при создании PDF', {
           description: generationResult.error,
         });
+        reportFailures(generationResult.failures);
         setIsGenerating(false);
       }
     } catch (error) {
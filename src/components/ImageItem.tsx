@@ -1,7 +1,8 @@
 import { useState } from 'react';
-import { GripVertical, X, ImageIcon } from 'lucide-react';
+import { GripVertical, X, ImageIcon, FileText } from 'lucide-react';
 import { Card, CardContent } from './ui/card';
 import { Button } from './ui/button';
+import { Input } from './ui/input';
 import { useAppStore } from '@/store/useAppStore';
 import { formatFileSize } from '@/types';
 import type { ImageItem as ImageItemType } from '@/types';
@@ -14,7 +15,8 @@ interface ImageItemProps {
 
 export function ImageItem({ image }: ImageItemProps) {
   const [isHovered, setIsHovered] = useState(false);
-  const { removeImage } = useAppStore();
+  const { removeImage, updateImagePageRange } = useAppStore();
+  const isPdf = image.info?.format === 'PDF';
 
   // Setup sortable functionality
   const {
@@ -63,6 +65,8 @@ export function ImageItem({ image }: ImageItemProps) {
                 alt={image.name}
                 className="w-full h-full object-cover"
               />
+            ) : isPdf ? (
+              <FileText className="w-6 h-6 text-muted-foreground" />
             ) : (
               <ImageIcon className="w-6 h-6 text-muted-foreground" />
             )}
@@ -79,12 +83,25 @@ export function ImageItem({ image }: ImageItemProps) {
                 {image.info.width} × {image.info.height} •{' '}
                 {formatFileSize(image.info.sizeBytes)} •{' '}
                 {image.info.format}
+                {image.info.frameCount > 1 &&
+                  ` • ${image.info.frameCount} ${isPdf ? 'стр.' : 'кадров'}`}
               </p>
             ) : (
               <p className="text-xs text-muted-foreground">Загрузка...</p>
             )}
           </div>
 
+          {/* Pages to copy from a PDF */}
+          {isPdf && (
+            <Input
+              className="w-28 h-8 flex-shrink-0"
+              placeholder="Все стр."
+              title="Страницы, например 1-3, 5, 8-"
+              value={image.pageRange ?? ''}
+              onChange={(e) => updateImagePageRange(image.id, e.target.value)}
+            />
+          )}
+
           {/* Delete button (visible on hover) */}
           {isHovered && (
             <Button
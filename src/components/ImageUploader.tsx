@@ -42,9 +42,11 @@ export function ImageUploader() {
             const info = await getImageInfo(path);
             updateImageInfo(path, info);
 
-            // Get thumbnail
-            const thumbnail = await getImageThumbnail(path, 96);
-            updateImageThumbnail(path, thumbnail);
+            // Get thumbnail (PDF pages have no preview)
+            if (info.format !== 'PDF') {
+              const thumbnail = await getImageThumbnail(path, 96);
+              updateImageThumbnail(path, thumbnail);
+            }
           } catch (err) {
             console.error(`Failed to get info for ${path}:`, err);
           }
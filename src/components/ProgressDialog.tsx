@@ -6,11 +6,24 @@ import {
   DialogTitle,
 } from './ui/dialog';
 import { Progress } from './ui/progress';
-import { useIsGenerating, useProgress } from '@/store/useAppStore';
+import { Button } from './ui/button';
+import {
+  useCurrentJobId,
+  useIsGenerating,
+  useProgress,
+} from '@/store/useAppStore';
+import { cancelGeneration } from '@/lib/tauri';
 
 export function ProgressDialog() {
   const isGenerating = useIsGenerating();
   const progress = useProgress();
+  const jobId = useCurrentJobId();
+
+  const handleCancel = () => {
+    if (jobId) {
+      cancelGeneration(jobId);
+    }
+  };
 
   return (
     <Dialog open={isGenerating}>
@@ -27,6 +40,11 @@ export function ProgressDialog() {
           <p className="text-center text-sm text-muted-foreground">
             {progress}%
           </p>
+          <div className="flex justify-end">
+            <Button variant="outline" onClick={handleCancel} disabled={!jobId}>
+              Отмена
+            </Button>
+          </div>
         </div>
       </DialogContent>
     </Dialog>
@@ -9,15 +9,55 @@ import {
 } from './ui/select';
 import { RadioGroup, RadioGroupItem } from './ui/radio-group';
 import { Switch } from './ui/switch';
+import { Button } from './ui/button';
 import { useSettings, useAppStore } from '@/store/useAppStore';
-import type { PageSize, Orientation, FitMode } from '@/types';
+import { selectPdf } from '@/lib/tauri';
+import { extractFileName } from '@/types';
+import type {
+  PageSize,
+  Orientation,
+  FitMode,
+  Alignment,
+  Margins,
+  GridLayout,
+  FillOrder,
+  FrameSelection,
+  Encryption,
+  EncryptionAlgorithm,
+  Permissions,
+  ErrorMode,
+} from '@/types';
+
+const DEFAULT_MARGINS: Margins = { top: 0, right: 0, bottom: 0, left: 0, unit: 'Mm' };
+
+const DEFAULT_ENCRYPTION: Encryption = {
+  userPassword: '',
+  algorithm: 'Aes256',
+  permissions: { print: true, copy: true, modify: true },
+};
+
+const DEFAULT_GRID: GridLayout = {
+  rows: 2,
+  columns: 2,
+  gutter: 5,
+  unit: 'Mm',
+  fillOrder: 'RowMajor',
+};
+
+const MARGIN_SIDES: Array<{ side: 'top' | 'right' | 'bottom' | 'left'; label: string }> = [
+  { side: 'top', label: 'Сверху' },
+  { side: 'right', label: 'Справа' },
+  { side: 'bottom', label: 'Снизу' },
+  { side: 'left', label: 'Слева' },
+];
 
 export function SettingsForm() {
   const settings = useSettings();
   const { updateSettings } = useAppStore();
 
   const handlePageSizeChange = (value: PageSize) => {
-    updateSettings({ pageSize: value });
+    // Grid layouts need a fixed page size
+    updateSettings(value === 'MatchImage' ? { pageSize: value, grid: undefined } : { pageSize: value });
   };
 
   const handleOrientationChange = (value: Orientation) => {
@@ -42,10 +82,176 @@ export function SettingsForm() {
     }
   };
 
+  const margins = settings.margins ?? DEFAULT_MARGINS;
+
+  const handleMarginChange =
+    (side: 'top' | 'right' | 'bottom' | 'left') => (e: React.ChangeEvent<HTMLInputElement>) => {
+      const value = parseFloat(e.target.value);
+      if (!isNaN(value) && value >= 0) {
+        updateSettings({ margins: { ...margins, [side]: value } });
+      }
+    };
+
+  const handleMarginUnitChange = (value: 'Mm' | 'Pt') => {
+    updateSettings({ margins: { ...margins, unit: value } });
+  };
+
+  const handleAlignmentChange = (value: Alignment) => {
+    updateSettings({ alignment: value });
+  };
+
+  const handleGridEnabledChange = (checked: boolean) => {
+    updateSettings({ grid: checked ? DEFAULT_GRID : undefined });
+  };
+
+  const handleGridNumberChange =
+    (field: 'rows' | 'columns' | 'gutter') => (e: React.ChangeEvent<HTMLInputElement>) => {
+      const value = parseFloat(e.target.value);
+      const valid = field === 'gutter' ? value >= 0 : Number.isInteger(value) && value >= 1;
+      if (settings.grid && !isNaN(value) && valid) {
+        updateSettings({ grid: { ...settings.grid, [field]: value } });
+      }
+    };
+
+  const handleFillOrderChange = (value: FillOrder) => {
+    if (settings.grid) {
+      updateSettings({ grid: { ...settings.grid, fillOrder: value } });
+    }
+  };
+
+  const frames = settings.frames ?? 'All';
+  const frameMode = typeof frames === 'string' ? frames : 'Range';
+
+  const handleFrameModeChange = (value: 'All' | 'First' | 'Range') => {
+    const selection: FrameSelection =
+      value === 'Range' ? { Range: { start: 1, end: 1 } } : value;
+    updateSettings({ frames: selection });
+  };
+
+  const handleFrameRangeChange =
+    (bound: 'start' | 'end') => (e: React.ChangeEvent<HTMLInputElement>) => {
+      const value = parseInt(e.target.value, 10);
+      if (typeof frames !== 'string' && !isNaN(value) && value >= 1) {
+        updateSettings({ frames: { Range: { ...frames.Range, [bound]: value } } });
+      }
+    };
+
+  const handleFallbackDpiChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    const value = parseFloat(e.target.value);
+    if (!isNaN(value) && value > 0) {
+      updateSettings({ fallbackDpi: value });
+    }
+  };
+
+  const handleWorkersChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    const value = parseInt(e.target.value, 10);
+    updateSettings({ workers: !isNaN(value) && value > 0 ? value : undefined });
+  };
+
+  const handleOnErrorChange = (value: ErrorMode) => {
+    updateSettings({ onError: value });
+  };
+
+  const handleMetadataChange =
+    (field: 'title' | 'author' | 'subject' | 'keywords') =>
+    (e: React.ChangeEvent<HTMLInputElement>) => {
+      updateSettings({
+        metadata: { ...settings.metadata, [field]: e.target.value || undefined },
+      });
+    };
+
+  const handleEncryptionToggle = (checked: boolean) => {
+    updateSettings({ encryption: checked ? DEFAULT_ENCRYPTION : undefined });
+  };
+
+  // The update keeps the existing file's security and conformance as they are
+  const handleAppendToggle = async (checked: boolean) => {
+    if (!checked) {
+      updateSettings({ appendTo: undefined });
+      return;
+    }
+    const path = await selectPdf();
+    if (path) {
+      updateSettings({ appendTo: { path }, encryption: undefined, compliance: undefined });
+    }
+  };
+
+  const handleAppendFileChange = async () => {
+    const path = await selectPdf();
+    if (path && settings.appendTo) {
+      updateSettings({ appendTo: { ...settings.appendTo, path } });
+    }
+  };
+
+  // Shown counted from 1; empty appends after the last page
+  const handleInsertBeforeChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    const value = parseInt(e.target.value, 10);
+    const index = !isNaN(value) && value >= 1 ? value - 1 : undefined;
+    if (settings.appendTo) {
+      updateSettings({ appendTo: { ...settings.appendTo, index } });
+    }
+  };
+
+  // PDF/A does not allow encryption or placeholder pages with a non-embedded font
+  const handleComplianceChange = (checked: boolean) => {
+    updateSettings(
+      checked
+        ? {
+            compliance: 'PdfA2b',
+            encryption: undefined,
+            ...(settings.onError === 'Placeholder' && { onError: 'Skip' as const }),
+          }
+        : { compliance: undefined }
+    );
+  };
+
+  const handleUserPasswordChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    if (settings.encryption) {
+      updateSettings({ encryption: { ...settings.encryption, userPassword: e.target.value } });
+    }
+  };
+
+  const handleOwnerPasswordChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    if (settings.encryption) {
+      updateSettings({
+        encryption: { ...settings.encryption, ownerPassword: e.target.value || undefined },
+      });
+    }
+  };
+
+  const handleEncryptionAlgorithmChange = (value: EncryptionAlgorithm) => {
+    if (settings.encryption) {
+      updateSettings({ encryption: { ...settings.encryption, algorithm: value } });
+    }
+  };
+
+  const handlePermissionChange = (permission: keyof Permissions) => (checked: boolean) => {
+    if (settings.encryption) {
+      updateSettings({
+        encryption: {
+          ...settings.encryption,
+          permissions: { ...settings.encryption.permissions, [permission]: checked },
+        },
+      });
+    }
+  };
+
   const handleOptimizeImagesChange = (checked: boolean) => {
     updateSettings({ optimizeImages: checked });
   };
 
+  const handleObjectStreamsChange = (checked: boolean) => {
+    updateSettings({ objectStreams: checked });
+  };
+
+  const handleTransparencyChange = (checked: boolean) => {
+    updateSettings({ transparency: checked ? 'Preserve' : 'Flatten' });
+  };
+
+  const handleBackgroundColorChange = (e: React.ChangeEvent<HTMLInputElement>) => {
+    updateSettings({ backgroundColor: e.target.value });
+  };
+
   return (
     <div className="space-y-6">
       {/* Page Size */}
@@ -62,6 +268,7 @@ export function SettingsForm() {
             <SelectItem value="Letter">Letter (8.5 × 11")</SelectItem>
             <SelectItem value="Legal">Legal (8.5 × 14")</SelectItem>
             <SelectItem value="Custom">Пользовательский</SelectItem>
+            <SelectItem value="MatchImage">По размеру изображения</SelectItem>
           </SelectContent>
         </Select>
       </div>
@@ -122,6 +329,12 @@ export function SettingsForm() {
               Альбомная (Landscape)
             </Label>
           </div>
+          <div className="flex items-center space-x-2">
+            <RadioGroupItem value="Auto" id="auto-orientation" />
+            <Label htmlFor="auto-orientation" className="font-normal cursor-pointer">
+              Автоматически (по изображению)
+            </Label>
+          </div>
         </RadioGroup>
       </div>
 
@@ -153,10 +366,271 @@ export function SettingsForm() {
               <div className="space-y-0.5">
                 <p className="font-medium">Оригинал</p>
                 <p className="text-xs text-muted-foreground whitespace-normal">
-                  Использовать оригинальный размер
+                  Физический размер с учётом DPI изображения
                 </p>
               </div>
             </SelectItem>
+            <SelectItem value="ShrinkOnly">
+              <div className="space-y-0.5">
+                <p className="font-medium">Только уменьшать</p>
+                <p className="text-xs text-muted-foreground whitespace-normal">
+                  Вписать, но не увеличивать маленькие изображения
+                </p>
+              </div>
+            </SelectItem>
+            <SelectItem value="FitWidth">
+              <div className="space-y-0.5">
+                <p className="font-medium">По ширине</p>
+                <p className="text-xs text-muted-foreground whitespace-normal">
+                  Растянуть до ширины страницы
+                </p>
+              </div>
+            </SelectItem>
+            <SelectItem value="FitHeight">
+              <div className="space-y-0.5">
+                <p className="font-medium">По высоте</p>
+                <p className="text-xs text-muted-foreground whitespace-normal">
+                  Растянуть до высоты страницы
+                </p>
+              </div>
+            </SelectItem>
+            <SelectItem value="Stretch">
+              <div className="space-y-0.5">
+                <p className="font-medium">Растянуть</p>
+                <p className="text-xs text-muted-foreground whitespace-normal">
+                  Заполнить страницу без сохранения пропорций
+                </p>
+              </div>
+            </SelectItem>
+          </SelectContent>
+        </Select>
+      </div>
+
+      {/* Alignment */}
+      <div className="space-y-2">
+        <Label htmlFor="alignment">Выравнивание</Label>
+        <Select value={settings.alignment ?? 'Center'} onValueChange={handleAlignmentChange}>
+          <SelectTrigger id="alignment">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            <SelectItem value="TopLeft">Сверху слева</SelectItem>
+            <SelectItem value="Top">Сверху по центру</SelectItem>
+            <SelectItem value="TopRight">Сверху справа</SelectItem>
+            <SelectItem value="Left">По центру слева</SelectItem>
+            <SelectItem value="Center">По центру</SelectItem>
+            <SelectItem value="Right">По центру справа</SelectItem>
+            <SelectItem value="BottomLeft">Снизу слева</SelectItem>
+            <SelectItem value="Bottom">Снизу по центру</SelectItem>
+            <SelectItem value="BottomRight">Снизу справа</SelectItem>
+          </SelectContent>
+        </Select>
+      </div>
+
+      {/* Margins */}
+      <div className="space-y-3 p-4 bg-muted rounded-lg">
+        <div className="flex items-center justify-between">
+          <p className="text-sm font-medium">Поля</p>
+          <Select value={margins.unit} onValueChange={handleMarginUnitChange}>
+            <SelectTrigger id="margin-unit" className="w-20 h-8">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              <SelectItem value="Mm">мм</SelectItem>
+              <SelectItem value="Pt">pt</SelectItem>
+            </SelectContent>
+          </Select>
+        </div>
+        <div className="grid grid-cols-2 gap-3">
+          {MARGIN_SIDES.map(({ side, label }) => (
+            <div key={side} className="space-y-2">
+              <Label htmlFor={`margin-${side}`} className="text-xs">
+                {label}
+              </Label>
+              <Input
+                id={`margin-${side}`}
+                type="number"
+                min="0"
+                step="1"
+                value={margins[side]}
+                onChange={handleMarginChange(side)}
+              />
+            </div>
+          ))}
+        </div>
+      </div>
+
+      {/* Grid Layout */}
+      <div className="space-y-3">
+        <div className="flex items-center justify-between space-x-2">
+          <div className="space-y-0.5">
+            <Label htmlFor="grid-enabled">Несколько изображений на странице</Label>
+            <p className="text-xs text-muted-foreground">
+              Сетка для контактных листов и архивов чеков
+            </p>
+          </div>
+          <Switch
+            id="grid-enabled"
+            checked={settings.grid !== undefined}
+            disabled={settings.pageSize === 'MatchImage'}
+            onCheckedChange={handleGridEnabledChange}
+          />
+        </div>
+
+        {settings.grid && (
+          <div className="space-y-3 p-4 bg-muted rounded-lg">
+            <div className="grid grid-cols-3 gap-3">
+              <div className="space-y-2">
+                <Label htmlFor="grid-rows" className="text-xs">
+                  Строки
+                </Label>
+                <Input
+                  id="grid-rows"
+                  type="number"
+                  min="1"
+                  step="1"
+                  value={settings.grid.rows}
+                  onChange={handleGridNumberChange('rows')}
+                />
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="grid-columns" className="text-xs">
+                  Столбцы
+                </Label>
+                <Input
+                  id="grid-columns"
+                  type="number"
+                  min="1"
+                  step="1"
+                  value={settings.grid.columns}
+                  onChange={handleGridNumberChange('columns')}
+                />
+              </div>
+              <div className="space-y-2">
+                <Label htmlFor="grid-gutter" className="text-xs">
+                  Отступ (мм)
+                </Label>
+                <Input
+                  id="grid-gutter"
+                  type="number"
+                  min="0"
+                  step="1"
+                  value={settings.grid.gutter}
+                  onChange={handleGridNumberChange('gutter')}
+                />
+              </div>
+            </div>
+            <Select value={settings.grid.fillOrder} onValueChange={handleFillOrderChange}>
+              <SelectTrigger id="fill-order">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="RowMajor">По строкам</SelectItem>
+                <SelectItem value="ColumnMajor">По столбцам</SelectItem>
+              </SelectContent>
+            </Select>
+          </div>
+        )}
+      </div>
+
+      {/* Frames */}
+      <div className="space-y-2">
+        <Label htmlFor="frames">Многостраничные TIFF и анимации</Label>
+        <Select value={frameMode} onValueChange={handleFrameModeChange}>
+          <SelectTrigger id="frames">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            <SelectItem value="All">Все кадры</SelectItem>
+            <SelectItem value="First">Только первый кадр</SelectItem>
+            <SelectItem value="Range">Диапазон кадров</SelectItem>
+          </SelectContent>
+        </Select>
+
+        {typeof frames !== 'string' && (
+          <div className="grid grid-cols-2 gap-3">
+            <div className="space-y-2">
+              <Label htmlFor="frames-start" className="text-xs">
+                С кадра
+              </Label>
+              <Input
+                id="frames-start"
+                type="number"
+                min="1"
+                step="1"
+                value={frames.Range.start}
+                onChange={handleFrameRangeChange('start')}
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="frames-end" className="text-xs">
+                По кадр
+              </Label>
+              <Input
+                id="frames-end"
+                type="number"
+                min="1"
+                step="1"
+                value={frames.Range.end}
+                onChange={handleFrameRangeChange('end')}
+              />
+            </div>
+          </div>
+        )}
+      </div>
+
+      {/* Fallback DPI */}
+      <div className="flex items-center justify-between space-x-2">
+        <div className="space-y-0.5">
+          <Label htmlFor="fallback-dpi">DPI по умолчанию</Label>
+          <p className="text-xs text-muted-foreground">
+            Для изображений без сведений о разрешении
+          </p>
+        </div>
+        <Input
+          id="fallback-dpi"
+          type="number"
+          min="1"
+          step="1"
+          className="w-24"
+          value={settings.fallbackDpi ?? 72}
+          onChange={handleFallbackDpiChange}
+        />
+      </div>
+
+      {/* Workers */}
+      <div className="flex items-center justify-between space-x-2">
+        <div className="space-y-0.5">
+          <Label htmlFor="workers">Потоки обработки</Label>
+          <p className="text-xs text-muted-foreground">
+            Сколько изображений готовить параллельно (пусто — по числу ядер)
+          </p>
+        </div>
+        <Input
+          id="workers"
+          type="number"
+          min="1"
+          step="1"
+          placeholder="авто"
+          className="w-24"
+          value={settings.workers ?? ''}
+          onChange={handleWorkersChange}
+        />
+      </div>
+
+      {/* Error Handling */}
+      <div className="space-y-2">
+        <Label htmlFor="on-error">Если изображение не читается</Label>
+        <Select value={settings.onError ?? 'Abort'} onValueChange={handleOnErrorChange}>
+          <SelectTrigger id="on-error">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            <SelectItem value="Abort">Прервать создание PDF</SelectItem>
+            <SelectItem value="Skip">Пропустить изображение</SelectItem>
+            <SelectItem value="Placeholder" disabled={settings.compliance !== undefined}>
+              Вставить страницу-заглушку
+            </SelectItem>
           </SelectContent>
         </Select>
       </div>
@@ -175,6 +649,220 @@ export function SettingsForm() {
           onCheckedChange={handleOptimizeImagesChange}
         />
       </div>
+
+      {/* Object Streams */}
+      <div className="flex items-center justify-between space-x-2">
+        <div className="space-y-0.5">
+          <Label htmlFor="object-streams">Компактная структура</Label>
+          <p className="text-xs text-muted-foreground">
+            Сжимать служебные объекты PDF в потоки объектов (PDF 1.5+)
+          </p>
+        </div>
+        <Switch
+          id="object-streams"
+          checked={settings.objectStreams ?? false}
+          onCheckedChange={handleObjectStreamsChange}
+        />
+      </div>
+
+      {/* Transparency */}
+      <div className="space-y-3">
+        <div className="flex items-center justify-between space-x-2">
+          <div className="space-y-0.5">
+            <Label htmlFor="preserve-transparency">Сохранять прозрачность</Label>
+            <p className="text-xs text-muted-foreground">
+              Прозрачные области PNG, WebP и GIF остаются прозрачными
+            </p>
+          </div>
+          <Switch
+            id="preserve-transparency"
+            checked={(settings.transparency ?? 'Preserve') === 'Preserve'}
+            onCheckedChange={handleTransparencyChange}
+          />
+        </div>
+
+        {settings.transparency === 'Flatten' && (
+          <div className="flex items-center justify-between space-x-2">
+            <Label htmlFor="background-color" className="text-xs">
+              Цвет фона
+            </Label>
+            <Input
+              id="background-color"
+              type="color"
+              className="w-16 h-8 p-1"
+              value={settings.backgroundColor ?? '#ffffff'}
+              onChange={handleBackgroundColorChange}
+            />
+          </div>
+        )}
+      </div>
+
+      {/* Document Metadata */}
+      <div className="space-y-3">
+        <Label>Свойства документа</Label>
+        {(
+          [
+            ['title', 'Название'],
+            ['author', 'Автор'],
+            ['subject', 'Тема'],
+            ['keywords', 'Ключевые слова'],
+          ] as const
+        ).map(([field, label]) => (
+          <div key={field} className="space-y-2">
+            <Label htmlFor={`metadata-${field}`} className="text-xs">
+              {label}
+            </Label>
+            <Input
+              id={`metadata-${field}`}
+              value={settings.metadata?.[field] ?? ''}
+              onChange={handleMetadataChange(field)}
+            />
+          </div>
+        ))}
+      </div>
+
+      {/* Append to Existing PDF */}
+      <div className="space-y-3">
+        <div className="flex items-center justify-between space-x-2">
+          <div className="space-y-0.5">
+            <Label htmlFor="append-to">Дописать в существующий PDF</Label>
+            <p className="text-xs text-muted-foreground">
+              Исходные страницы и подписи не изменяются; свойства документа остаются прежними
+            </p>
+          </div>
+          <Switch
+            id="append-to"
+            checked={settings.appendTo !== undefined}
+            onCheckedChange={handleAppendToggle}
+          />
+        </div>
+
+        {settings.appendTo && (
+          <>
+            <div className="flex items-center justify-between space-x-2">
+              <span className="text-xs truncate" title={settings.appendTo.path}>
+                {extractFileName(settings.appendTo.path)}
+              </span>
+              <Button variant="outline" size="sm" onClick={handleAppendFileChange}>
+                Выбрать файл
+              </Button>
+            </div>
+            <div className="flex items-center justify-between space-x-2">
+              <Label htmlFor="insert-before" className="text-xs">
+                Вставить перед страницей
+              </Label>
+              <Input
+                id="insert-before"
+                type="number"
+                min="1"
+                step="1"
+                placeholder="в конец"
+                className="w-24"
+                value={settings.appendTo.index !== undefined ? settings.appendTo.index + 1 : ''}
+                onChange={handleInsertBeforeChange}
+              />
+            </div>
+          </>
+        )}
+      </div>
+
+      {/* PDF/A */}
+      <div className="flex items-center justify-between space-x-2">
+        <div className="space-y-0.5">
+          <Label htmlFor="compliance">PDF/A-2b</Label>
+          <p className="text-xs text-muted-foreground">
+            Формат для долговременного архивного хранения
+          </p>
+        </div>
+        <Switch
+          id="compliance"
+          disabled={settings.appendTo !== undefined}
+          checked={settings.compliance === 'PdfA2b'}
+          onCheckedChange={handleComplianceChange}
+        />
+      </div>
+
+      {/* Password Protection */}
+      <div className="space-y-3">
+        <div className="flex items-center justify-between space-x-2">
+          <div className="space-y-0.5">
+            <Label htmlFor="encryption">Защита паролем</Label>
+            <p className="text-xs text-muted-foreground">
+              Шифрование AES; пароли не сохраняются между запусками
+            </p>
+          </div>
+          <Switch
+            id="encryption"
+            disabled={settings.compliance !== undefined || settings.appendTo !== undefined}
+            checked={settings.encryption !== undefined}
+            onCheckedChange={handleEncryptionToggle}
+          />
+        </div>
+
+        {settings.encryption && (
+          <>
+            <div className="space-y-2">
+              <Label htmlFor="user-password" className="text-xs">
+                Пароль для открытия
+              </Label>
+              <Input
+                id="user-password"
+                type="password"
+                autoComplete="new-password"
+                value={settings.encryption.userPassword}
+                onChange={handleUserPasswordChange}
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="owner-password" className="text-xs">
+                Пароль владельца
+              </Label>
+              <Input
+                id="owner-password"
+                type="password"
+                autoComplete="new-password"
+                value={settings.encryption.ownerPassword ?? ''}
+                onChange={handleOwnerPasswordChange}
+              />
+            </div>
+            <div className="space-y-2">
+              <Label htmlFor="encryption-algorithm" className="text-xs">
+                Алгоритм
+              </Label>
+              <Select
+                value={settings.encryption.algorithm}
+                onValueChange={handleEncryptionAlgorithmChange}
+              >
+                <SelectTrigger id="encryption-algorithm">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="Aes256">AES-256</SelectItem>
+                  <SelectItem value="Aes128">AES-128 (старые программы)</SelectItem>
+                </SelectContent>
+              </Select>
+            </div>
+            {(
+              [
+                ['print', 'Разрешить печать'],
+                ['copy', 'Разрешить копирование'],
+                ['modify', 'Разрешить изменение'],
+              ] as const
+            ).map(([permission, label]) => (
+              <div key={permission} className="flex items-center justify-between space-x-2">
+                <Label htmlFor={`permission-${permission}`} className="text-xs">
+                  {label}
+                </Label>
+                <Switch
+                  id={`permission-${permission}`}
+                  checked={settings.encryption?.permissions[permission] ?? true}
+                  onCheckedChange={handlePermissionChange(permission)}
+                />
+              </div>
+            ))}
+          </>
+        )}
+      </div>
     </div>
   );
 }
@@ -1,10 +1,12 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { open, save } from '@tauri-apps/plugin-dialog';
 import type {
   ValidationResult,
   ImageInfo,
   PdfSettings,
   GenerationResult,
+  GenerationProgress,
 } from '@/types';
 
 // ============================================================================
@@ -50,23 +52,48 @@ export async function getImageThumbnail(
 
 /**
  * Generate PDF from images
+ * @param jobId - Identifier used for progress events and cancellation
  * @param imagePaths - Array of absolute paths to images (in order)
  * @param outputPath - Absolute path where to save the PDF
  * @param settings - PDF generation settings
  * @returns GenerationResult with success status and optional error
  */
 export async function generatePdf(
+  jobId: string,
   imagePaths: string[],
   outputPath: string,
   settings: PdfSettings
 ): Promise<GenerationResult> {
   return await invoke<GenerationResult>('generate_pdf', {
+    jobId,
     imagePaths,
     outputPath,
     settings,
   });
 }
 
+/**
+ * Cancel a running PDF generation job
+ * @param jobId - Identifier passed to generatePdf
+ * @returns true if the job was running
+ */
+export async function cancelGeneration(jobId: string): Promise<boolean> {
+  return await invoke<boolean>('cancel_generation', { jobId });
+}
+
+/**
+ * Subscribe to PDF generation progress events
+ * @param handler - Called for every progress event
+ * @returns Function that removes the listener
+ */
+export async function onGenerationProgress(
+  handler: (progress: GenerationProgress) => void
+): Promise<UnlistenFn> {
+  return await listen<GenerationProgress>('generation-progress', (event) =>
+    handler(event.payload)
+  );
+}
+
 // ============================================================================
 // File Dialogs
 // ============================================================================
@@ -82,7 +109,28 @@ export async function selectImages(): Promise<string[] | null> {
     filters: [
       {
         name: 'Images',
-        extensions: ['png', 'jpg', 'jpeg', 'webp', 'bmp', 'gif', 'tiff', 'tif'],
+        extensions: [
+          'png',
+          'jpg',
+          'jpeg',
+          'webp',
+          'bmp',
+          'gif',
+          'tiff',
+          'tif',
+          // Need a build with the `heif` feature
+          'heic',
+          'heif',
+          'avif',
+          'svg',
+          // Pages are copied into the output
+          'pdf',
+        ],
+      },
+      // Scanners may save images without an extension; the backend checks content
+      {
+        name: 'All files',
+        extensions: ['*'],
       },
     ],
   });
@@ -95,6 +143,25 @@ export async function selectImages(): Promise<string[] | null> {
   return Array.isArray(selected) ? selected : [selected];
 }
 
+/**
+ * Open file picker dialog to select an existing PDF
+ * @returns Selected file path, or null if cancelled
+ */
+export async function selectPdf(): Promise<string | null> {
+  const selected = await open({
+    multiple: false,
+    directory: false,
+    filters: [
+      {
+        name: 'PDF Document',
+        extensions: ['pdf'],
+      },
+    ],
+  });
+
+  return typeof selected === 'string' ? selected : null;
+}
+
 /**
  * Open save dialog to select output PDF path
  * @param defaultName - Default file name for the PDF
@@ -22,6 +22,7 @@ interface AppState {
   // UI state
   isGenerating: boolean;
   progress: number;
+  currentJobId: string | null;
 
   // Actions - Image Management
   addImages: (paths: string[]) => void;
@@ -30,6 +31,7 @@ interface AppState {
   reorderImages: (startIndex: number, endIndex: number) => void;
   updateImageInfo: (pathOrId: string, info: ImageItem['info']) => void;
   updateImageThumbnail: (pathOrId: string, thumbnail: string) => void;
+  updateImagePageRange: (id: string, pageRange: string) => void;
 
   // Actions - Settings Management
   updateSettings: (settings: Partial<PdfSettings>) => void;
@@ -38,6 +40,7 @@ interface AppState {
   // Actions - UI State
   setIsGenerating: (isGenerating: boolean) => void;
   setProgress: (progress: number) => void;
+  setCurrentJobId: (jobId: string | null) => void;
 }
 
 // ============================================================================
@@ -55,6 +58,7 @@ export const useAppStore = create<AppState>()(
       settings: DEFAULT_PDF_SETTINGS,
       isGenerating: false,
       progress: 0,
+      currentJobId: null,
 
       // ======================================================================
       // Image Management Actions
@@ -122,6 +126,14 @@ export const useAppStore = create<AppState>()(
         }));
       },
 
+      updateImagePageRange: (id: string, pageRange: string) => {
+        set((state) => ({
+          images: state.images.map((img) =>
+            img.id === id ? { ...img, pageRange } : img
+          ),
+        }));
+      },
+
       // ======================================================================
       // Settings Management Actions
       // ======================================================================
@@ -154,12 +166,16 @@ export const useAppStore = create<AppState>()(
       setProgress: (progress: number) => {
         set({ progress: Math.min(Math.max(progress, 0), 100) });
       },
+
+      setCurrentJobId: (currentJobId: string | null) => {
+        set({ currentJobId });
+      },
     }),
     {
       name: 'image-pdf-storage',
-      // Only persist settings, not images or UI state
+      // Only persist settings, not images or UI state; passwords stay in memory
       partialize: (state) => ({
-        settings: state.settings,
+        settings: { ...state.settings, encryption: undefined },
       }),
     }
   )
@@ -178,6 +194,8 @@ export const useSettings = () => useAppStore((state) => state.settings);
 // Select only UI state - split into separate selectors to avoid object creation
 export const useIsGenerating = () => useAppStore((state) => state.isGenerating);
 export const useProgress = () => useAppStore((state) => state.progress);
+export const useCurrentJobId = () =>
+  useAppStore((state) => state.currentJobId);
 
 // Select image count
 export const useImageCount = () =>
@@ -5,17 +5,146 @@
 /**
  * Supported page sizes for PDF generation
  */
-export type PageSize = 'A4' | 'A3' | 'A5' | 'Letter' | 'Legal' | 'Custom';
+export type PageSize = 'A4' | 'A3' | 'A5' | 'Letter' | 'Legal' | 'Custom' | 'MatchImage';
 
 /**
  * Page orientation
  */
-export type Orientation = 'Portrait' | 'Landscape';
+export type Orientation = 'Portrait' | 'Landscape' | 'Auto';
 
 /**
  * Image fit modes for PDF pages
  */
-export type FitMode = 'Fit' | 'Fill' | 'Original';
+export type FitMode =
+  | 'Fit'
+  | 'Fill'
+  | 'Original'
+  | 'ShrinkOnly'
+  | 'FitWidth'
+  | 'FitHeight'
+  | 'Stretch';
+
+/**
+ * Anchor point of an image inside the content area
+ */
+export type Alignment =
+  | 'TopLeft'
+  | 'Top'
+  | 'TopRight'
+  | 'Left'
+  | 'Center'
+  | 'Right'
+  | 'BottomLeft'
+  | 'Bottom'
+  | 'BottomRight';
+
+/**
+ * Unit for lengths in settings (millimeters or points)
+ */
+export type LengthUnit = 'Mm' | 'Pt';
+
+/**
+ * Frames of multi-page TIFF and animated GIF/WebP files to add as pages.
+ * Range bounds are inclusive and counted from 1.
+ */
+export type FrameSelection = 'All' | 'First' | { Range: { start: number; end: number } };
+
+/**
+ * Order in which consecutive images fill grid cells
+ */
+export type FillOrder = 'RowMajor' | 'ColumnMajor';
+
+/**
+ * Several images per page, packed into a grid of equal cells
+ */
+export interface GridLayout {
+  rows: number;
+  columns: number;
+  /** Space between cells, in `unit` */
+  gutter: number;
+  unit: LengthUnit;
+  fillOrder: FillOrder;
+}
+
+/**
+ * Page margins around the content area
+ */
+export interface Margins {
+  top: number;
+  right: number;
+  bottom: number;
+  left: number;
+  unit: LengthUnit;
+}
+
+/**
+ * Document properties written to the Info dictionary and XMP metadata.
+ * Dates are RFC 3339 strings.
+ */
+export interface DocumentMetadata {
+  title?: string;
+  author?: string;
+  subject?: string;
+  keywords?: string;
+  /** Application that created the original content */
+  creator?: string;
+  /** Defaults to the PDF engine and its version */
+  producer?: string;
+  /** Defaults to the time of generation */
+  creationDate?: string;
+  /** Defaults to the creation date */
+  modificationDate?: string;
+}
+
+/**
+ * Standard security handler cipher
+ */
+export type EncryptionAlgorithm = 'Aes128' | 'Aes256';
+
+/**
+ * What readers may do without the owner password
+ */
+export interface Permissions {
+  print: boolean;
+  copy: boolean;
+  modify: boolean;
+}
+
+/**
+ * Password protection for the generated PDF
+ */
+export interface Encryption {
+  /** Password needed to open the document; empty opens without one */
+  userPassword: string;
+  /** Password that lifts the permission restrictions (default: random) */
+  ownerPassword?: string;
+  algorithm: EncryptionAlgorithm;
+  permissions: Permissions;
+}
+
+/**
+ * Archival standard the output must conform to
+ */
+export type Compliance = 'PdfA2b';
+
+/**
+ * Existing PDF that new pages are added to with an incremental update
+ */
+export interface AppendTo {
+  path: string;
+  /** Zero-based page the new pages are inserted before (default: after the last page) */
+  index?: number;
+}
+
+/**
+ * What to do with an image that cannot be read, decoded or embedded
+ */
+export type ErrorMode = 'Abort' | 'Skip' | 'Placeholder';
+
+/**
+ * Handling of images with an alpha channel
+ */
+export type TransparencyMode = 'Preserve' | 'Flatten';
 
 // ============================================================================
 // PDF Settings
@@ -25,7 +154,7 @@ export type FitMode = 'Fit' | 'Fill' | 'Original';
  * PDF generation settings
  */
 export interface PdfSettings {
-  /** Page size (A4, A3, A5, Letter, Legal, Custom) */
+  /** Page size (A4, A3, A5, Letter, Legal, Custom, or MatchImage to size each page to its image) */
   pageSize: PageSize;
 
   /** Custom page width in millimeters (only used if pageSize is 'Custom') */
@@ -34,7 +163,7 @@ export interface PdfSettings {
   /** Custom page height in millimeters (only used if pageSize is 'Custom') */
   customHeight?: number;
 
-  /** Page orientation (Portrait or Landscape) */
+  /** Page orientation (Portrait, Landscape, or Auto to follow each image) */
   orientation: Orientation;
 
   /** How to fit images on the page */
@@ -42,6 +171,51 @@ export interface PdfSettings {
 
   /** Enable image optimization (compression for PNG/JPEG) */
   optimizeImages?: boolean;
+
+  /** Keep transparency or flatten onto the background color (default: Preserve) */
+  transparency?: TransparencyMode;
+
+  /** Background color for flattening, as #RRGGBB (default: #ffffff) */
+  backgroundColor?: string;
+
+  /** Resolution assumed for images that do not declare one (default: 72) */
+  fallbackDpi?: number;
+
+  /** Page margins (default: none) */
+  margins?: Margins;
+
+  /** Where images are anchored inside the margins (default: Center) */
+  alignment?: Alignment;
+
+  /** Grid layout for several images per page (default: one image per page) */
+  grid?: GridLayout;
+
+  /** Frames of multi-page and animated images to add (default: All) */
+  frames?: FrameSelection;
+
+  /** Title, author and other document properties (default: none) */
+  metadata?: DocumentMetadata;
+
+  /** Passwords and permissions (default: not encrypted, never persisted) */
+  encryption?: Encryption;
+
+  /** Archival conformance, e.g. PDF/A-2b (default: plain PDF; excludes encryption) */
+  compliance?: Compliance;
+
+  /** Pack objects into compressed object streams (default: false; ignored when encrypted) */
+  objectStreams?: boolean;
+
+  /** Threads preparing images in parallel (default: one per CPU core) */
+  workers?: number;
+
+  /** Stop, leave the image out, or add a placeholder page (default: Abort; no placeholders with PDF/A) */
+  onError?: ErrorMode;
+
+  /** Pages to copy from PDF inputs by path, e.g. `1-3, 5, 8-` (default: all pages) */
+  pageRanges?: Record<string, string>;
+
+  /** Add the pages to an existing PDF, keeping its content and signatures (default: new document; excludes encryption and PDF/A) */
+  appendTo?: AppendTo;
 }
 
 /**
@@ -52,6 +226,12 @@ export const DEFAULT_PDF_SETTINGS: PdfSettings = {
   orientation: 'Portrait',
   fitMode: 'Fit',
   optimizeImages: true,
+  transparency: 'Preserve',
+  backgroundColor: '#ffffff',
+  fallbackDpi: 72,
+  margins: { top: 0, right: 0, bottom: 0, left: 0, unit: 'Mm' },
+  alignment: 'Center',
+  frames: 'All',
 };
 
 // ============================================================================
@@ -62,10 +242,10 @@ export const DEFAULT_PDF_SETTINGS: PdfSettings = {
  * Image metadata information
  */
 export interface ImageInfo {
-  /** Image width in pixels */
+  /** Image width in pixels; for a PDF, the first page in points */
   width: number;
 
-  /** Image height in pixels */
+  /** Image height in pixels; for a PDF, the first page in points */
   height: number;
 
   /** Image format (PNG, JPEG, WEBP, etc.) */
@@ -73,6 +253,9 @@ export interface ImageInfo {
 
   /** File size in bytes */
   sizeBytes: number;
+
+  /** Pages of a PDF or TIFF file or frames of an animated GIF/WebP, otherwise 1 */
+  frameCount: number;
 }
 
 /**
@@ -93,6 +276,9 @@ export interface ImageItem {
 
   /** Base64 thumbnail data URL (populated after loading) */
   thumbnail?: string;
+
+  /** Pages to copy from a PDF input, e.g. `1-3, 5`; all pages when empty */
+  pageRange?: string;
 }
 
 // ============================================================================
@@ -113,6 +299,84 @@ export interface ValidationResult {
   }>;
 }
 
+/**
+ * Summary of a finished generation job
+ */
+export interface GenerationStats {
+  pageCount: number;
+
+  /** Size of the finished PDF in bytes */
+  fileSize: number;
+
+  /** Wall-clock time of the whole job, in milliseconds */
+  elapsedMs: number;
+
+  /** Images that reused an identical image already embedded in the file */
+  duplicatesCollapsed: number;
+
+  /** Sizes and encoding of every image added, in input order */
+  images: ImageStats[];
+
+  /** Bytes removed by PNG/JPEG optimization over all added images */
+  bytesSaved: number;
+
+  /** Image data that repeated images did not add a second time */
+  duplicateBytesSaved: number;
+
+  /** Images left out or replaced by a placeholder, in input order */
+  failures: ImageFailure[];
+}
+
+/**
+ * How an image is stored in the PDF: original JPEG, JPEG re-encoded by
+ * optimization, decoded pixels compressed with Flate, or an SVG drawing as
+ * vector content
+ */
+export type ImageEncoding =
+  | 'DctPassthrough'
+  | 'DctReencoded'
+  | 'Flate'
+  | 'Vector';
+
+/**
+ * How one input image ended up in the PDF
+ */
+export interface ImageStats {
+  /** Zero-based index of the image in the input list */
+  index: number;
+
+  path: string;
+
+  /** Size of the input file in bytes */
+  originalBytes: number;
+
+  /** Image data the file added to the PDF; 0 when it repeats an earlier image */
+  embeddedBytes: number;
+
+  encoding: ImageEncoding;
+
+  /** Bytes removed by PNG/JPEG optimization before embedding */
+  optimizationSaved: number;
+}
+
+/**
+ * An image that could not be added to the document
+ */
+export interface ImageFailure {
+  /** Zero-based index of the image in the input list */
+  index: number;
+
+  path: string;
+
+  /** Backend error type, e.g. 'ImageReadError' */
+  kind: string;
+
+  message: string;
+
+  /** Failing frame of a multi-page or animated image, counted from 1; earlier frames stay in the PDF */
+  frame?: number;
+}
+
 /**
  * Result of PDF generation
  */
@@ -125,6 +389,43 @@ export interface GenerationResult {
 
   /** Error message (if failed) */
   error?: string;
+
+  /** Images skipped or replaced by a placeholder, also when generation failed afterwards */
+  failures: ImageFailure[];
+
+  /** Pages added, sizes, timing and skipped images (if successful) */
+  stats?: GenerationStats;
+}
+
+/**
+ * Stage of a running generation job
+ */
+export type ProgressStage = 'Processing' | 'Embedded' | 'Failed' | 'Saving' | 'Finished';
+
+/**
+ * Progress event emitted by the backend during PDF generation
+ */
+export interface GenerationProgress {
+  /** Job the event belongs to */
+  jobId: string;
+
+  /** Zero-based index of the current image */
+  index: number;
+
+  /** Total number of images in the job */
+  total: number;
+
+  /** Path of the current image */
+  path: string;
+
+  /** Current stage */
+  stage: ProgressStage;
+
+  /** Bytes written to the output so far, or the final file size once finished */
+  bytesWritten: number;
+
+  /** Why the image could not be added (at the 'Failed' stage) */
+  failure?: ImageFailure;
 }
 
 // ============================================================================